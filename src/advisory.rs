@@ -0,0 +1,656 @@
+// Copyright (c) 2023 audit-check developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or https://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use crate::{
+    config::Ignore,
+    cvss::{self, Severity},
+    error::{AuditCheckError, Error},
+    lockfile::Lockfile,
+    policy::{Finding, Policies, apply},
+};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt};
+
+mod text;
+
+/// The `cargo audit --json` report, only the parts we use are deserialized.
+#[derive(Clone, Debug, Deserialize)]
+struct Report {
+    vulnerabilities: Vulnerabilities,
+    #[serde(default)]
+    warnings: BTreeMap<String, Vec<Warning>>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct Vulnerabilities {
+    #[serde(default)]
+    list: Vec<Vulnerability>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct Vulnerability {
+    advisory: Advisory,
+    versions: Versions,
+    package: Package,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct Warning {
+    kind: AdvisoryKind,
+    package: Package,
+    advisory: Option<Advisory>,
+    versions: Option<Versions>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct Advisory {
+    id: String,
+    title: String,
+    date: String,
+    url: Option<String>,
+    #[serde(default)]
+    aliases: Vec<String>,
+    cvss: Option<String>,
+}
+
+/// The version requirements an advisory is fixed, or never applied, in.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct Versions {
+    /// The requirements of the fixed versions, i.e. `>=0.6.3`
+    #[serde(default)]
+    pub patched: Vec<String>,
+    /// The requirements of the versions never affected
+    #[serde(default)]
+    pub unaffected: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct Package {
+    name: String,
+    version: String,
+}
+
+/// The kind of advisory, vulnerabilities or one of the informational warnings.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum AdvisoryKind {
+    /// A security vulnerability
+    Vulnerability,
+    /// Unsound code
+    Unsound,
+    /// An unmaintained crate
+    Unmaintained,
+    /// A yanked crate version
+    Yanked,
+    /// Informational notices, and any warning kind unknown to us.
+    #[serde(other)]
+    Notice,
+}
+
+/// How urgently an advisory should be looked at.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum Priority {
+    /// Unmaintained or yanked crates and notices
+    Low,
+    /// Unsound code
+    Medium,
+    /// Vulnerabilities
+    High,
+}
+
+impl AdvisoryKind {
+    pub(crate) const ALL: [Self; 5] = [
+        Self::Vulnerability,
+        Self::Unsound,
+        Self::Unmaintained,
+        Self::Yanked,
+        Self::Notice,
+    ];
+
+    /// The kind as `cargo audit` names it, i.e. `unmaintained`.
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Vulnerability => "vulnerability",
+            Self::Unsound => "unsound",
+            Self::Unmaintained => "unmaintained",
+            Self::Yanked => "yanked",
+            Self::Notice => "notice",
+        }
+    }
+
+    /// Only vulnerabilities are high priority, the informational warnings are
+    /// lower, with unsound code ranking above unmaintained or yanked crates.
+    #[must_use]
+    pub fn priority(self) -> Priority {
+        match self {
+            Self::Vulnerability => Priority::High,
+            Self::Unsound => Priority::Medium,
+            Self::Unmaintained | Self::Yanked | Self::Notice => Priority::Low,
+        }
+    }
+}
+
+impl fmt::Display for AdvisoryKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Priority {
+    /// The priority in lowercase, i.e. `high`.
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+        }
+    }
+}
+
+/// The advisory database a report was made against.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all(deserialize = "kebab-case"))]
+#[non_exhaustive]
+pub struct Database {
+    /// The number of advisories in the database
+    pub advisory_count: usize,
+    /// The advisory-db commit the database was fetched at
+    pub last_commit: Option<String>,
+    /// When the database was last updated, i.e. `2023-03-01T10:00:00Z`
+    pub last_updated: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct DatabaseReport {
+    database: Option<Database>,
+}
+
+/// A single advisory reported by `cargo audit`.
+#[derive(Clone, Debug, Serialize)]
+#[non_exhaustive]
+pub struct Rustsec {
+    /// The advisory id, i.e. `RUSTSEC-2020-0099`, or the crate and version of a yanked crate
+    pub id: String,
+    /// The advisory page, or the crates.io page of a yanked crate
+    pub url: String,
+    /// The affected crate
+    #[serde(rename = "crate")]
+    pub krate: String,
+    /// The affected version of the crate
+    pub version: String,
+    /// Whether this is a vulnerability or an informational warning
+    pub kind: AdvisoryKind,
+    /// A one line description of the advisory
+    pub title: String,
+    /// When the advisory was published, i.e. `2020-12-10`
+    pub date: String,
+    /// Other ids of the advisory, i.e. CVE or GHSA ids
+    pub aliases: Vec<String>,
+    /// The CVSS vector, i.e. `CVSS:3.1/AV:N/AC:H/PR:N/UI:N/S:U/C:H/I:H/A:H`
+    pub cvss: Option<String>,
+    /// The versions the advisory is fixed, or never applied, in
+    pub versions: Versions,
+    /// How to resolve the advisory, i.e. `Upgrade to >=0.6.3`
+    pub solution: String,
+    /// Paths from the affected crate up through its dependents, see
+    /// [`AuditReport::add_dependency_paths`]
+    pub dependency_paths: Vec<Vec<String>>,
+}
+
+/// The advisories reported by `cargo audit --json`.
+///
+/// ```
+/// use audit_check::{AdvisoryKind, AuditReport};
+///
+/// # fn main() -> Result<(), audit_check::Error> {
+/// let report = AuditReport::parse(
+///     r#"{"vulnerabilities": {"list": [{
+///         "advisory": {"id": "RUSTSEC-2020-0099", "title": "Aovec<T> lacks bound", "date": "2020-12-10"},
+///         "versions": {"patched": []},
+///         "package": {"name": "aovec", "version": "1.1.0"}
+///     }]}}"#,
+/// )?;
+/// assert_eq!(AdvisoryKind::Vulnerability, report.advisories[0].kind);
+/// assert_eq!("No fixed upgrade is available!", report.advisories[0].solution);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Serialize)]
+#[non_exhaustive]
+pub struct AuditReport {
+    /// The vulnerabilities and informational warnings, sorted by id
+    pub advisories: Vec<Rustsec>,
+    /// The advisory database the audit ran against, when reported
+    pub database: Option<Database>,
+}
+
+impl AuditReport {
+    /// Parse the `cargo audit --json` output.
+    ///
+    /// # Errors
+    ///
+    /// The output is not a `cargo audit` JSON report.
+    pub fn parse(output: &str) -> Result<Self, Error> {
+        Ok(Self {
+            advisories: parse_rustsecs(output).map_err(|e| Error::AuditOutput {
+                reason: e.to_string(),
+            })?,
+            database: parse_database(output),
+        })
+    }
+
+    /// Fill in the dependency paths of each advisory from the contents of
+    /// the audited `Cargo.lock`.
+    ///
+    /// # Errors
+    ///
+    /// The lockfile can't be parsed.
+    pub fn add_dependency_paths(&mut self, lockfile: &str) -> Result<(), Error> {
+        let lockfile = Lockfile::parse(lockfile).map_err(|e| Error::Lockfile {
+            reason: e.to_string(),
+        })?;
+        add_dependency_paths(&mut self.advisories, &lockfile);
+        Ok(())
+    }
+
+    /// Decide what to do with each advisory, see [`Policies`] and [`Ignore`].
+    ///
+    /// ```
+    /// use audit_check::{AuditReport, Ignore, Policies, Policy};
+    ///
+    /// # fn main() -> Result<(), audit_check::Error> {
+    /// let report = AuditReport::parse(
+    ///     r#"{"vulnerabilities": {"list": [{
+    ///         "advisory": {"id": "RUSTSEC-2020-0099", "title": "Aovec<T> lacks bound", "date": "2020-12-10"},
+    ///         "versions": {"patched": []},
+    ///         "package": {"name": "aovec", "version": "1.1.0"}
+    ///     }]}}"#,
+    /// )?;
+    /// let findings = report.evaluate(Policies::from_deny("warnings"), &[]);
+    /// assert!(findings[0].is_failed());
+    ///
+    /// let ignore = Ignore::new("RUSTSEC-2020-0099", "only used in tests", "audit.toml");
+    /// let findings = report.evaluate(Policies::from_deny("warnings"), &[ignore]);
+    /// assert_eq!(Policy::Ignore, findings[0].decision.policy);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn evaluate(&self, policies: Policies, ignore: &[Ignore]) -> Vec<Finding> {
+        apply(policies, ignore, self.advisories.clone())
+    }
+}
+
+impl Rustsec {
+    fn new(
+        kind: AdvisoryKind,
+        package: Package,
+        advisory: Option<Advisory>,
+        versions: Option<Versions>,
+    ) -> Self {
+        let Package { name, version } = package;
+        let (id, url, title, date, aliases, cvss) = match advisory {
+            Some(advisory) => {
+                let url = advisory_url(&advisory.id, advisory.url);
+                (
+                    advisory.id,
+                    url,
+                    advisory.title,
+                    advisory.date,
+                    advisory.aliases,
+                    advisory.cvss,
+                )
+            }
+            // yanked crates have no advisory
+            None => (
+                format!("{name} {version}"),
+                format!("https://crates.io/crates/{name}/{version}"),
+                format!("{name} {version} has been yanked"),
+                String::new(),
+                vec![],
+                None,
+            ),
+        };
+        let versions = versions.unwrap_or_default();
+        let solution = solution(&versions);
+
+        Self {
+            id,
+            url,
+            krate: name,
+            version,
+            kind,
+            title,
+            date,
+            aliases,
+            cvss,
+            versions,
+            solution,
+            dependency_paths: vec![],
+        }
+    }
+}
+
+impl Rustsec {
+    /// The CVSS base score, when the advisory has a supported CVSS vector.
+    pub(crate) fn score(&self) -> Option<f64> {
+        self.cvss.as_deref().and_then(cvss::score)
+    }
+
+    /// The severity of the CVSS base score.
+    pub(crate) fn severity(&self) -> Option<Severity> {
+        self.score().map(Severity::from_score)
+    }
+}
+
+fn advisory_url(id: &str, url: Option<String>) -> String {
+    if id.starts_with("RUSTSEC-") {
+        format!("https://rustsec.org/advisories/{id}")
+    } else {
+        url.unwrap_or_default()
+    }
+}
+
+fn solution(versions: &Versions) -> String {
+    if versions.patched.is_empty() {
+        "No fixed upgrade is available!".to_string()
+    } else {
+        format!("Upgrade to {}", versions.patched.join(" OR "))
+    }
+}
+
+/// Parse the `cargo audit --json` output into the reported advisories, sorted by id.
+///
+/// The plain text report is parsed as well, so output passed through a
+/// wrapper that drops `--json` still works.
+pub(crate) fn parse(output: &str) -> Result<Vec<Rustsec>> {
+    parse_rustsecs(output).map_err(|e| {
+        AuditCheckError::AuditOutput {
+            reason: e.to_string(),
+        }
+        .into()
+    })
+}
+
+fn parse_rustsecs(output: &str) -> Result<Vec<Rustsec>, serde_json::Error> {
+    let report: Report = match serde_json::from_str(output) {
+        Ok(report) => report,
+        Err(_) if text::is_text(output) => return Ok(sorted(text::parse(output))),
+        Err(e) => return Err(e),
+    };
+
+    let vulnerabilities = report.vulnerabilities.list.into_iter().map(|v| {
+        Rustsec::new(
+            AdvisoryKind::Vulnerability,
+            v.package,
+            Some(v.advisory),
+            Some(v.versions),
+        )
+    });
+    let warnings = report
+        .warnings
+        .into_values()
+        .flatten()
+        .map(|w| Rustsec::new(w.kind, w.package, w.advisory, w.versions));
+
+    Ok(sorted(vulnerabilities.chain(warnings).collect()))
+}
+
+fn sorted(mut rustsecs: Vec<Rustsec>) -> Vec<Rustsec> {
+    rustsecs.sort_by(|a, b| (&a.id, &a.krate, &a.version).cmp(&(&b.id, &b.krate, &b.version)));
+    rustsecs
+}
+
+/// The advisory database from the `cargo audit --json` output, if reported.
+pub(crate) fn parse_database(output: &str) -> Option<Database> {
+    serde_json::from_str::<DatabaseReport>(output)
+        .ok()
+        .and_then(|report| report.database)
+}
+
+/// Fill in the dependency paths of each advisory from the lockfile.
+pub(crate) fn add_dependency_paths(rustsecs: &mut [Rustsec], lockfile: &Lockfile) {
+    for rustsec in rustsecs {
+        rustsec.dependency_paths = lockfile.dependency_paths(&rustsec.krate, &rustsec.version);
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::{AdvisoryKind, AuditReport, Priority, add_dependency_paths, parse, parse_database};
+    use crate::{
+        config::Ignore,
+        cvss::Severity,
+        error::Error,
+        lockfile::{Lockfile, test::TEST_LOCKFILE},
+        policy::{Policies, Policy},
+    };
+    use anyhow::Result;
+
+    pub(crate) use super::text::test::TEST_RUSTSEC;
+
+    pub(crate) const TEST_REPORT: &str = r#"{
+  "database": {
+    "advisory-count": 554,
+    "last-commit": "a9f5f1d5d5ba4d7f9e8b0f9d2e4a0c1b2d3e4f5a",
+    "last-updated": "2023-03-01T10:00:00Z"
+  },
+  "lockfile": { "dependency-count": 6 },
+  "settings": {},
+  "vulnerabilities": {
+    "found": true,
+    "count": 2,
+    "list": [
+      {
+        "advisory": {
+          "id": "RUSTSEC-2020-0099",
+          "package": "aovec",
+          "title": "Aovec<T> lacks bound on its Send and Sync traits allowing data races",
+          "date": "2020-12-10",
+          "aliases": ["CVE-2020-36206", "GHSA-5wg8-7c9q-794v"],
+          "url": "https://github.com/Ngk/aovec/issues/2",
+          "cvss": "CVSS:3.1/AV:N/AC:H/PR:N/UI:N/S:U/C:H/I:H/A:H",
+          "informational": null
+        },
+        "versions": { "patched": [], "unaffected": [] },
+        "package": { "name": "aovec", "version": "1.1.0" }
+      },
+      {
+        "advisory": {
+          "id": "RUSTSEC-2022-0040",
+          "package": "owning_ref",
+          "title": "Multiple soundness issues in `owning_ref`",
+          "date": "2022-01-26",
+          "aliases": ["GHSA-9qxh-258v-666c"],
+          "url": null,
+          "cvss": null,
+          "informational": null
+        },
+        "versions": { "patched": [], "unaffected": [] },
+        "package": { "name": "owning_ref", "version": "0.3.3" }
+      }
+    ]
+  },
+  "warnings": {
+    "unmaintained": [
+      {
+        "kind": "unmaintained",
+        "package": { "name": "anymap", "version": "0.12.1" },
+        "advisory": {
+          "id": "RUSTSEC-2021-0065",
+          "package": "anymap",
+          "title": "anymap is unmaintained.",
+          "date": "2021-05-07",
+          "aliases": [],
+          "url": null,
+          "cvss": null,
+          "informational": "unmaintained"
+        },
+        "versions": { "patched": [], "unaffected": [] }
+      }
+    ],
+    "unsound": [
+      {
+        "kind": "unsound",
+        "package": { "name": "smallvec", "version": "0.4.5" },
+        "advisory": {
+          "id": "RUSTSEC-2018-0018",
+          "package": "smallvec",
+          "title": "smallvec creates uninitialized value of any type",
+          "date": "2018-09-25",
+          "aliases": [],
+          "url": null,
+          "cvss": null,
+          "informational": "unsound"
+        },
+        "versions": { "patched": [">=0.6.3", "^0.3.4, <0.4.0"], "unaffected": ["<0.3.2"] }
+      }
+    ]
+  }
+}"#;
+
+    #[test]
+    fn parse_works() -> Result<()> {
+        let rustsecs = parse(TEST_REPORT)?;
+        assert_eq!(4, rustsecs.len());
+        let smallvec = &rustsecs[0];
+        assert_eq!("RUSTSEC-2018-0018", smallvec.id);
+        assert_eq!("smallvec", smallvec.krate);
+        assert_eq!(AdvisoryKind::Unsound, smallvec.kind);
+        assert_eq!(
+            "https://rustsec.org/advisories/RUSTSEC-2018-0018",
+            smallvec.url
+        );
+        assert_eq!("Upgrade to >=0.6.3 OR ^0.3.4, <0.4.0", smallvec.solution);
+        assert_eq!(vec!["<0.3.2"], smallvec.versions.unaffected);
+        assert_eq!(
+            vec!["CVE-2020-36206", "GHSA-5wg8-7c9q-794v"],
+            rustsecs[1].aliases
+        );
+        assert_eq!(Some(Severity::High), rustsecs[1].severity());
+        assert_eq!(None, smallvec.severity());
+        assert_eq!(AdvisoryKind::Vulnerability, rustsecs[1].kind);
+        assert_eq!("No fixed upgrade is available!", rustsecs[1].solution);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_yanked_works() -> Result<()> {
+        let report = r#"{
+  "vulnerabilities": { "found": false, "count": 0, "list": [] },
+  "warnings": {
+    "yanked": [
+      {
+        "kind": "yanked",
+        "package": { "name": "anymap", "version": "0.12.0" },
+        "advisory": null,
+        "versions": null
+      }
+    ]
+  }
+}"#;
+        let rustsecs = parse(report)?;
+        assert_eq!(1, rustsecs.len());
+        assert_eq!(AdvisoryKind::Yanked, rustsecs[0].kind);
+        assert_eq!("anymap 0.12.0", rustsecs[0].id);
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_warning_kinds_are_notices() -> Result<()> {
+        let report = TEST_REPORT.replace(r#""kind": "unsound""#, r#""kind": "fancy""#);
+        let rustsecs = parse(&report)?;
+        assert_eq!(AdvisoryKind::Notice, rustsecs[0].kind);
+        Ok(())
+    }
+
+    #[test]
+    fn priority_works() {
+        assert_eq!(Priority::High, AdvisoryKind::Vulnerability.priority());
+        assert!(AdvisoryKind::Unmaintained.priority() < AdvisoryKind::Vulnerability.priority());
+        assert!(AdvisoryKind::Unmaintained.priority() < AdvisoryKind::Unsound.priority());
+    }
+
+    #[test]
+    fn parse_fails_on_garbage() {
+        assert!(parse("error: couldn't fetch advisory database").is_err());
+    }
+
+    #[test]
+    fn parse_database_works() {
+        let database = parse_database(TEST_REPORT);
+        assert!(database.is_some());
+        let database = database.unwrap();
+        assert_eq!(554, database.advisory_count);
+        assert_eq!(
+            Some("a9f5f1d5d5ba4d7f9e8b0f9d2e4a0c1b2d3e4f5a"),
+            database.last_commit.as_deref()
+        );
+        assert!(parse_database("error: couldn't fetch advisory database").is_none());
+    }
+
+    #[test]
+    fn parse_falls_back_to_text() -> Result<()> {
+        let rustsecs =
+            parse("Crate:     anymap\nWarning:   unmaintained\nID:        RUSTSEC-2021-0065\n")?;
+        assert_eq!(1, rustsecs.len());
+        assert_eq!(AdvisoryKind::Unmaintained, rustsecs[0].kind);
+        Ok(())
+    }
+
+    #[test]
+    fn add_dependency_paths_works() -> Result<()> {
+        let mut rustsecs = parse(TEST_REPORT)?;
+        add_dependency_paths(&mut rustsecs, &Lockfile::parse(TEST_LOCKFILE)?);
+        assert_eq!(2, rustsecs[0].dependency_paths.len());
+        assert_eq!(1, rustsecs[1].dependency_paths.len());
+        Ok(())
+    }
+
+    #[test]
+    fn audit_report_works() -> Result<()> {
+        let mut report = AuditReport::parse(TEST_REPORT)?;
+        assert_eq!(4, report.advisories.len());
+        assert_eq!(
+            Some(554),
+            report.database.as_ref().map(|db| db.advisory_count)
+        );
+        report.add_dependency_paths(TEST_LOCKFILE)?;
+        assert_eq!(2, report.advisories[0].dependency_paths.len());
+        assert!(matches!(
+            report.add_dependency_paths("not a lockfile"),
+            Err(Error::Lockfile { .. })
+        ));
+        assert!(matches!(
+            AuditReport::parse("not a report"),
+            Err(Error::AuditOutput { .. })
+        ));
+
+        let ignore = Ignore::new("RUSTSEC-2020-0099", "only used in tests", "audit.toml");
+        let findings = report.evaluate(Policies::from_deny("unsound"), &[ignore]);
+        // highest priority first
+        assert_eq!(AdvisoryKind::Vulnerability, findings[0].rustsec.kind);
+        assert_eq!(Policy::Ignore, findings[0].decision.policy);
+        assert!(findings[0].decision.reason.contains("audit.toml"));
+        assert_eq!(
+            2,
+            findings
+                .iter()
+                .filter(|finding| finding.is_failed())
+                .count()
+        );
+        Ok(())
+    }
+}
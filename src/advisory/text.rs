@@ -0,0 +1,151 @@
+// Copyright (c) 2023 audit-check developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or https://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use super::{AdvisoryKind, Rustsec, Versions, solution};
+use regex::Regex;
+use std::sync::LazyLock;
+
+static CRATE_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"Crate: +(.*)").expect("Invalid CRATE_REGEX"));
+static VERSION_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"Version: +(.*)").expect("Invalid VERSION_REGEX"));
+static WARNING_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"Warning: +(.*)").expect("Invalid WARNING_REGEX"));
+static TITLE_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"Title: +(.*)").expect("Invalid TITLE_REGEX"));
+static DATE_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"Date: +(.*)").expect("Invalid DATE_REGEX"));
+static SOLUTION_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"Solution: +(.*)").expect("Invalid SOLUTION_REGEX"));
+static ID_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"ID: +(.*)").expect("Invalid ID_REGEX"));
+static URL_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"URL: +(https:.*)").expect("Invalid URL_REGEX"));
+
+/// Whether the output looks like the plain text `cargo audit` report.
+pub(super) fn is_text(output: &str) -> bool {
+    output.lines().any(|line| line.starts_with("Crate:"))
+}
+
+/// Parse the plain text `cargo audit` report, every advisory starts with a
+/// `Crate:` line.
+pub(super) fn parse(output: &str) -> Vec<Rustsec> {
+    let mut blocks: Vec<String> = vec![];
+    for line in output.lines() {
+        if line.starts_with("Crate:") {
+            blocks.push(String::new());
+        }
+        if let Some(block) = blocks.last_mut() {
+            block.push_str(line);
+            block.push('\n');
+        }
+    }
+    blocks.iter().map(|block| parse_rustsec(block)).collect()
+}
+
+fn parse_rustsec(rustsec_str: &str) -> Rustsec {
+    // unknown warnings are informational all the same
+    let kind = parse_caps(&WARNING_REGEX, rustsec_str).map_or(AdvisoryKind::Vulnerability, |w| {
+        AdvisoryKind::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == w)
+            .unwrap_or(AdvisoryKind::Notice)
+    });
+    let versions = Versions::default();
+
+    Rustsec {
+        id: parse_caps(&ID_REGEX, rustsec_str).unwrap_or_else(|| "No ID".to_string()),
+        url: parse_caps(&URL_REGEX, rustsec_str).unwrap_or_else(|| "No URL".to_string()),
+        krate: parse_caps(&CRATE_REGEX, rustsec_str).unwrap_or_else(|| "No Crate".to_string()),
+        version: parse_caps(&VERSION_REGEX, rustsec_str)
+            .unwrap_or_else(|| "No Version".to_string()),
+        kind,
+        title: parse_caps(&TITLE_REGEX, rustsec_str).unwrap_or_else(|| "No Title".to_string()),
+        date: parse_caps(&DATE_REGEX, rustsec_str).unwrap_or_else(|| "No Date".to_string()),
+        aliases: vec![],
+        cvss: None,
+        solution: parse_caps(&SOLUTION_REGEX, rustsec_str).unwrap_or_else(|| solution(&versions)),
+        versions,
+        dependency_paths: vec![],
+    }
+}
+
+fn parse_caps(regex: &Regex, rustsec_str: &str) -> Option<String> {
+    regex
+        .captures(rustsec_str)
+        .and_then(|caps| caps.get(1))
+        .map(|m| m.as_str().to_string())
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::parse;
+    use crate::reporter::issue::default_title as generate_title;
+
+    pub(crate) const TEST_RUSTSEC: &str = r"Crate:     aovec
+Version:   1.1.0
+Title:     Aovec<T> lacks bound on its Send and Sync traits allowing data races
+Date:      2020-12-10
+ID:        RUSTSEC-2020-0099
+URL:       https://rustsec.org/advisories/RUSTSEC-2020-0099
+Solution:  No fixed upgrade is available!
+Dependency tree:
+aovec 1.1.0
+└── audit-check-test 0.1.0
+
+Crate:     owning_ref
+Version:   0.3.3
+Title:     Multiple soundness issues in `owning_ref`
+Date:      2022-01-26
+ID:        RUSTSEC-2022-0040
+URL:       https://rustsec.org/advisories/RUSTSEC-2022-0040
+Solution:  No fixed upgrade is available!
+Dependency tree:
+owning_ref 0.3.3
+└── parking_lot 0.4.8
+    └── aovec 1.1.0
+        └── audit-check-test 0.1.0
+
+Crate:     anymap
+Version:   0.12.1
+Warning:   unmaintained
+Title:     anymap is unmaintained.
+Date:      2021-05-07
+ID:        RUSTSEC-2021-0065
+URL:       https://rustsec.org/advisories/RUSTSEC-2021-0065
+Dependency tree:
+anymap 0.12.1
+└── audit-check-test 0.1.0
+
+Crate:     smallvec
+Version:   0.4.5
+Warning:   unsound
+Title:     smallvec creates uninitialized value of any type
+Date:      2018-09-25
+ID:        RUSTSEC-2018-0018
+URL:       https://rustsec.org/advisories/RUSTSEC-2018-0018
+Dependency tree:
+smallvec 0.4.5
+└── aovec 1.1.0
+    └── audit-check-test 0.1.0
+";
+
+    #[test]
+    fn parse_works() {
+        assert_eq!(4, parse(TEST_RUSTSEC).len());
+    }
+
+    #[test]
+    fn generate_title_works() {
+        let rustsec_map = parse(TEST_RUSTSEC);
+        assert_eq!(
+            "RUSTSEC-2018-0018, RUSTSEC-2020-0099, RUSTSEC-2021-0065, RUSTSEC-2022-0040",
+            generate_title(&rustsec_map)
+        );
+    }
+}
@@ -8,73 +8,317 @@
 
 use anyhow::Result;
 use std::{
-    io::{BufRead, BufReader},
-    process::{ChildStderr, ChildStdout, ExitStatus, Stdio},
-    sync::mpsc::Sender,
-    thread,
+    env,
+    ffi::{OsStr, OsString},
+    fs,
+    path::{Path, PathBuf},
+    process::{self, Stdio},
+    sync::atomic::{AtomicUsize, Ordering},
     time::Duration,
 };
-use tracing::trace;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, BufReader},
+    process::{Child, Command},
+    signal, time,
+};
+use tracing::{debug, trace, warn};
+
+use crate::{
+    config::ignore::{AUDIT_TOML, without_ignores},
+    diagnostic::Diagnostic,
+    error::AuditCheckError,
+};
 
-use crate::{error::AuditCheckError, utils::handle_join_error};
+/// Numbers the scratch directories of a process.
+static SCRATCH: AtomicUsize = AtomicUsize::new(0);
 
-pub(crate) fn audit(
+/// The captured output of a `cargo audit` run.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct AuditOutput {
+    pub(crate) stdout: Vec<String>,
+    /// The classified stderr output.
+    pub(crate) diagnostics: Vec<Diagnostic>,
+    pub(crate) code: i32,
+}
+
+/// Run `cargo audit`, streaming its output as it is produced.
+///
+/// `cargo audit` is killed if it does not finish within the timeout, or when
+/// the check is cancelled, see [`cancelled`].
+pub(crate) async fn audit(deny: &str, timeout: Option<Duration>) -> Result<AuditOutput> {
+    audit_in("cargo", Path::new("."), deny, timeout).await
+}
+
+// `cargo audit` drops the advisories ignored in `.cargo/audit.toml` from its
+// report, so when the file ignores any it runs in a scratch directory with a
+// copy of the file without them, and audit-check applies them instead.
+async fn audit_in(
+    program: &str,
+    project: &Path,
     deny: &str,
-    tx_stdout: Sender<String>,
-    tx_stderr: Sender<String>,
-    tx_code: Sender<i32>,
-) -> Result<()> {
-    let command = format!("cargo audit -D{deny}");
-    trace!("Running '{command}'");
-    let mut cmd = std::process::Command::new("sh");
-    let _ = cmd.arg("-c");
-    let _ = cmd.arg(command);
+    timeout: Option<Duration>,
+) -> Result<AuditOutput> {
+    let mut args: Vec<OsString> = vec!["audit".into(), "--json".into(), format!("-D{deny}").into()];
+    match Scratch::without_ignores(project)? {
+        Some(scratch) => {
+            args.extend(["--file".into(), scratch.lockfile.clone().into_os_string()]);
+            run_in(Some(&scratch.dir), program, &args, timeout).await
+        }
+        None => run_in(Some(project), program, &args, timeout).await,
+    }
+}
+
+/// A directory `cargo audit` runs in with a copy of `.cargo/audit.toml`
+/// without its ignored advisories, removed when dropped.
+#[derive(Debug)]
+struct Scratch {
+    dir: PathBuf,
+    /// The project `Cargo.lock`
+    lockfile: PathBuf,
+}
+
+impl Scratch {
+    fn without_ignores(project: &Path) -> Result<Option<Self>> {
+        let Ok(contents) = fs::read_to_string(project.join(AUDIT_TOML)) else {
+            return Ok(None);
+        };
+        let lockfile = project.join("Cargo.lock");
+        // without a lockfile cargo audit generates one in the project, and a
+        // broken config file is reported by cargo audit itself
+        let (Ok(lockfile), Ok(Some(config))) =
+            (fs::canonicalize(lockfile), without_ignores(&contents))
+        else {
+            return Ok(None);
+        };
+        let dir = env::temp_dir().join(format!(
+            "audit-check-{}-{}",
+            process::id(),
+            SCRATCH.fetch_add(1, Ordering::Relaxed)
+        ));
+        let scratch = Self { dir, lockfile };
+        fs::create_dir_all(scratch.dir.join(".cargo"))?;
+        fs::write(scratch.dir.join(AUDIT_TOML), config)?;
+        debug!(
+            "Running cargo audit without the ignored advisories of '{AUDIT_TOML}' in '{}'",
+            scratch.dir.display()
+        );
+        Ok(Some(scratch))
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        if let Err(e) = fs::remove_dir_all(&self.dir) {
+            warn!("Unable to remove '{}': {e}", self.dir.display());
+        }
+    }
+}
+
+/// Run a command, streaming its output as it is produced, with the same
+/// timeout and cancellation as `cargo audit`.
+pub(crate) async fn run<S>(
+    program: &str,
+    args: &[S],
+    timeout: Option<Duration>,
+) -> Result<AuditOutput>
+where
+    S: AsRef<OsStr>,
+{
+    run_in(None, program, args, timeout).await
+}
+
+async fn run_in<S>(
+    dir: Option<&Path>,
+    program: &str,
+    args: &[S],
+    timeout: Option<Duration>,
+) -> Result<AuditOutput>
+where
+    S: AsRef<OsStr>,
+{
+    let command_line: Vec<_> = args
+        .iter()
+        .map(|arg| arg.as_ref().to_string_lossy())
+        .collect();
+    trace!("Running '{program} {}'", command_line.join(" "));
+    let mut cmd = Command::new(program);
+    let _ = cmd.args(args);
+    if let Some(dir) = dir {
+        let _ = cmd.current_dir(dir);
+    }
     let _ = cmd.stdout(Stdio::piped());
     let _ = cmd.stderr(Stdio::piped());
+    let _ = cmd.kill_on_drop(true);
 
     let mut child = cmd.spawn()?;
-
-    let stdout = child.stdout.take().ok_or(AuditCheckError::Stdout)?;
-    let stdout_handle = thread::spawn(move || handle_stdout(stdout, &tx_stdout));
-
-    let stderr = child.stderr.take().ok_or(AuditCheckError::Stderr)?;
-    let stderr_handle = thread::spawn(move || handle_stderr(stderr, &tx_stderr));
-
-    loop {
-        match child.try_wait() {
-            Ok(Some(exit_status)) => {
-                handle_status(exit_status, tx_code)?;
-                break;
-            }
-            Ok(None) => thread::sleep(Duration::from_millis(500)),
-            Err(e) => return Err(e.into()),
+    let result = tokio::select! {
+        result = output(&mut child, timeout) => result,
+        () = cancelled() => Err(AuditCheckError::Cancelled.into()),
+    };
+    if result.is_err() {
+        // a no-op when cargo audit has already exited
+        if let Err(e) = child.kill().await {
+            warn!("Unable to kill '{program}': {e}");
         }
     }
+    result
+}
 
-    stdout_handle.join().map_err(handle_join_error)??;
-    stderr_handle.join().map_err(handle_join_error)??;
-    Ok(())
+/// Resolves once the check is cancelled, with ctrl-c or the SIGTERM the
+/// Actions runner sends when a workflow run is cancelled.
+pub(crate) async fn cancelled() {
+    #[cfg(unix)]
+    if let Ok(mut terminate) = signal::unix::signal(signal::unix::SignalKind::terminate()) {
+        tokio::select! {
+            _ = signal::ctrl_c() => {}
+            _ = terminate.recv() => {}
+        }
+        return;
+    }
+    // never cancel when the handler can't be installed
+    if signal::ctrl_c().await.is_err() {
+        std::future::pending::<()>().await;
+    }
 }
 
-fn handle_stdout(stdout: ChildStdout, tx: &Sender<String>) -> Result<()> {
-    let stdout_reader = BufReader::new(stdout);
-    for line in stdout_reader.lines().map_while(Result::ok) {
-        tx.send(line)?;
+async fn output(child: &mut Child, timeout: Option<Duration>) -> Result<AuditOutput> {
+    let stdout = child.stdout.take().ok_or(AuditCheckError::Stdout)?;
+    let stderr = child.stderr.take().ok_or(AuditCheckError::Stderr)?;
+    let output = async {
+        let (stdout, diagnostics, status) = tokio::try_join!(
+            read_lines(stdout, |line| {
+                trace!("{line}");
+                line
+            }),
+            read_lines(stderr, |line| {
+                let diagnostic = Diagnostic::classify(&line);
+                diagnostic.log();
+                diagnostic
+            }),
+            async { child.wait().await.map_err(anyhow::Error::from) },
+        )?;
+        let code = status.code().ok_or(AuditCheckError::Code)?;
+        Ok(AuditOutput {
+            stdout,
+            diagnostics,
+            code,
+        })
+    };
+    match timeout {
+        Some(timeout) => time::timeout(timeout, output)
+            .await
+            .map_err(|_elapsed| AuditCheckError::Timeout { timeout })?,
+        None => output.await,
     }
-    Ok(())
 }
 
-fn handle_stderr(stderr: ChildStderr, tx: &Sender<String>) -> Result<()> {
-    let stderr_reader = BufReader::new(stderr);
-    for line in stderr_reader.lines().map_while(Result::ok) {
-        tx.send(line)?;
+// Each line is handled as soon as it is read, so output is logged while
+// cargo audit is still running.
+async fn read_lines<R, F, T>(reader: R, handle: F) -> Result<Vec<T>>
+where
+    R: AsyncRead + Unpin,
+    F: Fn(String) -> T,
+{
+    let mut lines = BufReader::new(reader).lines();
+    let mut buf = vec![];
+    while let Some(line) = lines.next_line().await? {
+        buf.push(handle(line));
     }
-    Ok(())
+    Ok(buf)
 }
 
-fn handle_status(exit_status: ExitStatus, tx: Sender<i32>) -> Result<()> {
-    let code = exit_status.code().ok_or(AuditCheckError::Code)?;
-    tx.send(code)?;
-    drop(tx);
-    Ok(())
+#[cfg(test)]
+mod test {
+    use super::{audit_in, run};
+    use crate::{
+        advisory::parse,
+        config::{
+            ignore::{self, AUDIT_TOML},
+            test::config,
+        },
+        diagnostic::DiagnosticKind,
+        error::AuditCheckError,
+        policy::{Policy, evaluate},
+    };
+    use anyhow::Result;
+    use std::{env, fs, process, time::Duration};
+
+    #[tokio::test]
+    async fn run_captures_output() -> Result<()> {
+        let output = run(
+            "sh",
+            &["-c", "echo out; echo 'error: boom' >&2; echo more; exit 3"],
+            None,
+        )
+        .await?;
+        assert_eq!(vec!["out", "more"], output.stdout);
+        assert_eq!(1, output.diagnostics.len());
+        assert_eq!(DiagnosticKind::Error, output.diagnostics[0].kind);
+        assert_eq!(3, output.code);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn run_times_out() {
+        let result = run("sleep", &["10"], Some(Duration::from_millis(100))).await;
+        assert!(matches!(
+            result.err().map(anyhow::Error::downcast::<AuditCheckError>),
+            Some(Ok(AuditCheckError::Timeout { .. }))
+        ));
+    }
+
+    #[tokio::test]
+    async fn run_within_timeout_works() -> Result<()> {
+        let output = run("sh", &["-c", "echo done"], Some(Duration::from_secs(10))).await?;
+        assert_eq!(vec!["done"], output.stdout);
+        assert_eq!(0, output.code);
+        Ok(())
+    }
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn audit_toml_ignores_are_attributed() -> Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let project = env::temp_dir().join(format!("audit-check-{}-project", process::id()));
+        fs::create_dir_all(project.join(".cargo"))?;
+        let audit_toml = "[advisories]\nignore = [\"RUSTSEC-2020-0099\"]\n";
+        fs::write(project.join(AUDIT_TOML), audit_toml)?;
+        fs::write(project.join("Cargo.lock"), "version = 3\n")?;
+        // like cargo audit, leaves out the advisories ignored in its config
+        let report = r#"{"vulnerabilities": {"list": [{
+            "advisory": {"id": "RUSTSEC-2020-0099", "title": "Aovec<T> lacks bound", "date": "2020-12-10"},
+            "versions": {"patched": []},
+            "package": {"name": "aovec", "version": "1.1.0"}
+        }]}}"#;
+        fs::write(project.join("report.json"), report)?;
+        let cargo = project.join("cargo");
+        fs::write(
+            &cargo,
+            format!(
+                "#!/bin/sh\n[ \"$4\" = --file ] || exit 9\n\
+                 grep -q RUSTSEC .cargo/audit.toml && echo '{{}}' && exit 0\n\
+                 cat '{}'\n",
+                project.join("report.json").display()
+            ),
+        )?;
+        fs::set_permissions(&cargo, fs::Permissions::from_mode(0o755))?;
+
+        let output = audit_in(&cargo.to_string_lossy(), &project, "warnings", None).await;
+        let mut config = config();
+        config.ignore = ignore::parse(audit_toml, AUDIT_TOML)?;
+        drop(fs::remove_dir_all(&project));
+
+        let output = output?;
+        assert_eq!(0, output.code);
+        let findings = evaluate(&config, parse(&output.stdout.join("\n"))?);
+        assert_eq!(1, findings.len());
+        assert_eq!(Policy::Ignore, findings[0].decision.policy);
+        assert!(
+            findings[0]
+                .decision
+                .reason
+                .contains("ignored by '.cargo/audit.toml'")
+        );
+        Ok(())
+    }
 }
@@ -0,0 +1,273 @@
+// Copyright (c) 2023 audit-check developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or https://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use super::installed::checked_audit_version;
+use crate::{audit::run, error::AuditCheckError};
+use anyhow::{Context as _, Result};
+use rustc_version::Version;
+use serde::Deserialize;
+use std::{
+    env,
+    ffi::OsString,
+    fmt, fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+use tracing::{info, warn};
+
+/// Whether, and which, cargo audit is installed when it is missing or too old.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(try_from = "InstallSetting")]
+pub(crate) enum Install {
+    /// Never install, fail instead
+    #[default]
+    Never,
+    /// Install the latest release
+    Latest,
+    /// Install the given release
+    Version(Version),
+}
+
+// `install = true` or `install = "0.21.2"` in the config file
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+enum InstallSetting {
+    Enabled(bool),
+    Version(String),
+}
+
+impl TryFrom<InstallSetting> for Install {
+    type Error = String;
+
+    fn try_from(setting: InstallSetting) -> Result<Self, Self::Error> {
+        match setting {
+            InstallSetting::Enabled(true) => Ok(Self::Latest),
+            InstallSetting::Enabled(false) => Ok(Self::Never),
+            InstallSetting::Version(version) => version.parse(),
+        }
+    }
+}
+
+impl FromStr for Install {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "false" | "never" => Ok(Self::Never),
+            "true" | "latest" => Ok(Self::Latest),
+            version => Version::parse(version.trim_start_matches('v'))
+                .map(Self::Version)
+                .map_err(|e| format!("expected true, false, latest or a cargo audit version: {e}")),
+        }
+    }
+}
+
+impl fmt::Display for Install {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Never => f.write_str("false"),
+            Self::Latest => f.write_str("latest"),
+            Self::Version(version) => write!(f, "{version}"),
+        }
+    }
+}
+
+/// The installed cargo audit version.
+///
+/// When cargo audit is missing or too old and installing is enabled, it is
+/// installed and the version is checked again. The install is killed when it
+/// does not finish within the timeout.
+pub(crate) async fn ensure_audit(
+    install: &Install,
+    command: &str,
+    timeout: Option<Duration>,
+) -> Result<Version> {
+    match checked_audit_version(command) {
+        Err(e) if *install != Install::Never => {
+            warn!("{e}, installing cargo audit {install}");
+            self::install(install, timeout).await?;
+            checked_audit_version(command)
+        }
+        checked => checked,
+    }
+}
+
+/// Install cargo audit into cargo's bin directory.
+///
+/// A pinned release is taken from the runner tool cache when present, and
+/// added to it after being built, so later runs skip the build.
+pub(crate) async fn install(install: &Install, timeout: Option<Duration>) -> Result<()> {
+    let bin_dir =
+        cargo_bin_dir().ok_or_else(|| install_error("unable to find cargo's bin directory"))?;
+    let cache = match install {
+        Install::Version(version) => env::var_os("RUNNER_TOOL_CACHE")
+            .filter(|tool_cache| !tool_cache.is_empty())
+            .map(|tool_cache| cache_dir(Path::new(&tool_cache), version)),
+        Install::Never | Install::Latest => None,
+    };
+
+    match cache {
+        Some(cache) => {
+            let cached = cache.join("bin").join(binary_name());
+            if cached.is_file() {
+                info!("Using the cached cargo audit at '{}'", cached.display());
+            } else {
+                cargo_install(install, Some(&cache), timeout).await?;
+            }
+            fs::create_dir_all(&bin_dir).map_err(|e| {
+                install_error(&format!("unable to create '{}': {e}", bin_dir.display()))
+            })?;
+            let _ = fs::copy(&cached, bin_dir.join(binary_name())).map_err(|e| {
+                install_error(&format!("unable to copy '{}': {e}", cached.display()))
+            })?;
+            Ok(())
+        }
+        None => cargo_install(install, None, timeout).await,
+    }
+}
+
+async fn cargo_install(
+    install: &Install,
+    root: Option<&Path>,
+    timeout: Option<Duration>,
+) -> Result<()> {
+    let args = install_args(install, root);
+    info!(
+        "Running 'cargo {}'",
+        args.iter()
+            .map(|arg| arg.to_string_lossy())
+            .collect::<Vec<_>>()
+            .join(" ")
+    );
+    let output = run("cargo", &args, timeout)
+        .await
+        .context("cargo install cargo-audit has failed")?;
+    if output.code == 0 {
+        Ok(())
+    } else {
+        Err(install_error(&format!(
+            "cargo install exited with code {}",
+            output.code
+        )))
+    }
+}
+
+fn install_args(install: &Install, root: Option<&Path>) -> Vec<OsString> {
+    let mut args: Vec<OsString> = vec!["install".into(), "cargo-audit".into(), "--locked".into()];
+    if let Install::Version(version) = install {
+        args.extend(["--version".into(), format!("={version}").into()]);
+    }
+    if let Some(root) = root {
+        args.extend(["--root".into(), root.as_os_str().to_os_string()]);
+    }
+    args
+}
+
+// `$RUNNER_TOOL_CACHE/cargo-audit/<version>`, the layout used by the actions toolkit
+fn cache_dir(tool_cache: &Path, version: &Version) -> PathBuf {
+    tool_cache.join("cargo-audit").join(version.to_string())
+}
+
+// cargo finds subcommands in `$CARGO_HOME/bin`
+fn cargo_bin_dir() -> Option<PathBuf> {
+    env::var_os("CARGO_HOME")
+        .filter(|cargo_home| !cargo_home.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".cargo")))
+        .map(|cargo_home| cargo_home.join("bin"))
+}
+
+fn binary_name() -> String {
+    format!("cargo-audit{}", env::consts::EXE_SUFFIX)
+}
+
+fn install_error(reason: &str) -> anyhow::Error {
+    AuditCheckError::AuditInstall {
+        reason: reason.to_string(),
+    }
+    .into()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Install, cache_dir, install_args};
+    use rustc_version::Version;
+    use std::{ffi::OsString, path::Path};
+
+    #[test]
+    fn from_str_works() {
+        assert_eq!(Ok(Install::Never), "false".parse());
+        assert_eq!(Ok(Install::Latest), "true".parse());
+        assert_eq!(Ok(Install::Latest), "latest".parse());
+        assert_eq!(
+            Ok(Install::Version(Version::new(0, 21, 2))),
+            "v0.21.2".parse()
+        );
+        assert!("sometimes".parse::<Install>().is_err());
+    }
+
+    #[test]
+    fn deserialize_works() {
+        #[derive(serde::Deserialize)]
+        struct Settings {
+            install: Install,
+        }
+        let install = |toml: &str| toml::from_str::<Settings>(toml).map(|s| s.install);
+        assert_eq!(
+            Ok(Install::Latest),
+            install("install = true").map_err(|_| ())
+        );
+        assert_eq!(
+            Ok(Install::Never),
+            install("install = false").map_err(|_| ())
+        );
+        assert_eq!(
+            Ok(Install::Version(Version::new(0, 21, 2))),
+            install("install = \"0.21.2\"").map_err(|_| ())
+        );
+        assert!(install("install = \"soon\"").is_err());
+    }
+
+    #[test]
+    fn install_args_works() {
+        let args = |install: &Install, root: Option<&Path>| -> Vec<String> {
+            install_args(install, root)
+                .into_iter()
+                .map(|arg: OsString| arg.to_string_lossy().to_string())
+                .collect()
+        };
+        assert_eq!(
+            vec!["install", "cargo-audit", "--locked"],
+            args(&Install::Latest, None)
+        );
+        assert_eq!(
+            vec![
+                "install",
+                "cargo-audit",
+                "--locked",
+                "--version",
+                "=0.21.2",
+                "--root",
+                "/opt/hostedtoolcache/cargo-audit/0.21.2"
+            ],
+            args(
+                &Install::Version(Version::new(0, 21, 2)),
+                Some(Path::new("/opt/hostedtoolcache/cargo-audit/0.21.2"))
+            )
+        );
+    }
+
+    #[test]
+    fn cache_dir_works() {
+        assert_eq!(
+            Path::new("/opt/hostedtoolcache/cargo-audit/0.21.2"),
+            cache_dir(Path::new("/opt/hostedtoolcache"), &Version::new(0, 21, 2))
+        );
+    }
+}
@@ -6,13 +6,19 @@
 // option. All files in the project carrying such notice may not be copied,
 // modified, or distributed except according to those terms.
 
+use crate::error::AuditCheckError;
 use anyhow::Result;
+use rustc_version::Version;
 use std::{
     ffi::OsStr,
     process::{Command, Stdio},
 };
 
-pub(crate) fn check_audit<S>(command: S) -> Result<bool>
+/// The oldest cargo audit with the `--json` report fields we depend on.
+pub(crate) const MIN_AUDIT_VERSION: &str = "0.18.0";
+
+/// The installed cargo audit version, as reported by the given command.
+pub(crate) fn audit_version<S>(command: S) -> Result<Version>
 where
     S: AsRef<OsStr>,
 {
@@ -23,24 +29,85 @@ where
     let _ = cmd.stderr(Stdio::piped());
 
     let out = cmd.output()?;
-    Ok(out.status.success())
+    if out.status.success() {
+        parse_version(&String::from_utf8_lossy(&out.stdout))
+    } else {
+        Err(AuditCheckError::AuditMissing.into())
+    }
+}
+
+/// The installed cargo audit version, failing when it is too old.
+pub(crate) fn checked_audit_version<S>(command: S) -> Result<Version>
+where
+    S: AsRef<OsStr>,
+{
+    let version = audit_version(command)?;
+    if check_audit_version(&version)? {
+        Ok(version)
+    } else {
+        Err(AuditCheckError::AuditVersionCheck {
+            found: version.to_string(),
+            required: MIN_AUDIT_VERSION,
+        }
+        .into())
+    }
+}
+
+pub(crate) fn check_audit_version(version: &Version) -> Result<bool> {
+    Ok(version >= &Version::parse(MIN_AUDIT_VERSION)?)
+}
+
+// i.e. 'cargo-audit-audit 0.21.2', the binary name varies between releases
+fn parse_version(output: &str) -> Result<Version> {
+    output
+        .split_whitespace()
+        .find_map(|word| Version::parse(word).ok())
+        .ok_or_else(|| {
+            AuditCheckError::AuditVersion {
+                output: output.trim().to_string(),
+            }
+            .into()
+        })
 }
 
 #[cfg(test)]
 mod test {
-    use super::check_audit;
+    use super::{audit_version, check_audit_version, parse_version};
+    use crate::error::AuditCheckError;
+    use anyhow::Result;
+    use rustc_version::Version;
 
     #[test]
-    fn check_audit_fails() {
-        let res = check_audit("blah -V");
-        assert!(res.is_ok());
-        assert!(!res.unwrap());
+    fn audit_version_fails() {
+        let res = audit_version("blah -V");
+        assert!(matches!(
+            res.err().map(anyhow::Error::downcast::<AuditCheckError>),
+            Some(Ok(AuditCheckError::AuditMissing))
+        ));
     }
 
     #[test]
-    fn check_audit_succeeds() {
-        let res = check_audit("rustc -Vv");
+    fn audit_version_succeeds() {
+        let res = audit_version("rustc -V");
         assert!(res.is_ok());
-        assert!(res.unwrap());
+    }
+
+    #[test]
+    fn parse_version_works() -> Result<()> {
+        assert_eq!(
+            Version::new(0, 21, 2),
+            parse_version("cargo-audit-audit 0.21.2\n")?
+        );
+        assert_eq!(Version::new(0, 17, 6), parse_version("cargo-audit 0.17.6")?);
+        assert!(parse_version("cargo-audit").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn check_audit_version_works() -> Result<()> {
+        assert!(check_audit_version(&Version::new(0, 21, 2))?);
+        assert!(check_audit_version(&Version::new(0, 18, 0))?);
+        assert!(!check_audit_version(&Version::new(0, 17, 6))?);
+        Ok(())
     }
 }
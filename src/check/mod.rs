@@ -6,5 +6,6 @@
 // option. All files in the project carrying such notice may not be copied,
 // modified, or distributed except according to those terms.
 
+pub(crate) mod install;
 pub(crate) mod installed;
 pub(crate) mod rustc;
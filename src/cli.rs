@@ -0,0 +1,239 @@
+// Copyright (c) 2023 audit-check developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or https://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use crate::{
+    advisory::AdvisoryKind, check::install::Install, email::SmtpTls, policy::Policy,
+    webhook::WebhookFormat,
+};
+use clap::{ArgAction, Parser, ValueEnum};
+use serde::Deserialize;
+use tracing::Level;
+
+/// Run `cargo audit` on your project.
+///
+/// Every flag falls back to the matching `INPUT_*` environment variable used
+/// when running as a GitHub Action.
+#[derive(Clone, Debug, Default, Parser)]
+#[command(author, version, about, long_about = None, after_help = ENV_HELP)]
+pub(crate) struct Cli {
+    /// Your GitHub token, which will allow issue creation
+    #[arg(long)]
+    pub(crate) token: Option<String>,
+    /// Exit with an error on: warnings (any), unmaintained, unsound, yanked
+    #[arg(long)]
+    pub(crate) deny: Option<String>,
+    /// Set the tracing output level: TRACE, DEBUG, INFO, WARN, ERROR
+    #[arg(long)]
+    pub(crate) level: Option<Level>,
+    /// The owner/repository issues are created in
+    #[arg(long)]
+    pub(crate) repository: Option<String>,
+    /// The GitHub API issues are created through, i.e. on GitHub Enterprise Server
+    #[arg(long)]
+    pub(crate) api_url: Option<String>,
+    /// Create an issue when a RUSTSEC warning is found
+    #[arg(long, action = ArgAction::Set, num_args = 0..=1, default_missing_value = "true")]
+    pub(crate) create_issue: Option<bool>,
+    /// Render the issue without calling GitHub
+    #[arg(long, action = ArgAction::Set, num_args = 0..=1, default_missing_value = "true")]
+    pub(crate) dry_run: Option<bool>,
+    /// The file the rendered issue is written to on a dry run
+    #[arg(long)]
+    pub(crate) dry_run_file: Option<String>,
+    /// A minijinja template for the issue title
+    #[arg(long)]
+    pub(crate) issue_title_template: Option<String>,
+    /// A minijinja template for the issue body
+    #[arg(long)]
+    pub(crate) issue_body_template: Option<String>,
+    /// A file containing the issue title template
+    #[arg(long)]
+    pub(crate) issue_title_template_file: Option<String>,
+    /// A file containing the issue body template
+    #[arg(long)]
+    pub(crate) issue_body_template_file: Option<String>,
+    /// A TOML config file, defaults to .github/audit-check.toml when present
+    #[arg(long)]
+    pub(crate) config: Option<String>,
+    /// The format the advisories are written to stdout in
+    #[arg(long, value_enum)]
+    pub(crate) format: Option<Format>,
+    /// Write a versioned JSON report of the check to this file
+    #[arg(long)]
+    pub(crate) report_file: Option<String>,
+    /// Write a `JUnit` XML report of the check to this file
+    #[arg(long)]
+    pub(crate) junit_file: Option<String>,
+    /// Write a `CycloneDX` SBOM of Cargo.lock with the advisories found to this file
+    #[arg(long)]
+    pub(crate) sbom_file: Option<String>,
+    /// Write the advisories found as OSV entries to this file
+    #[arg(long)]
+    pub(crate) osv_file: Option<String>,
+    /// Write a GitLab dependency scanning report to this file
+    #[arg(long)]
+    pub(crate) gitlab_file: Option<String>,
+    /// Write a standalone HTML report to this file
+    #[arg(long)]
+    pub(crate) html_report: Option<String>,
+    /// Post a digest of new and resolved advisories to this webhook when they change
+    #[arg(long)]
+    pub(crate) webhook_url: Option<String>,
+    /// The webhook payload flavor
+    #[arg(long, value_enum)]
+    pub(crate) webhook_format: Option<WebhookFormat>,
+    /// The file the advisories last posted to the webhook are kept in
+    #[arg(long)]
+    pub(crate) webhook_state_file: Option<String>,
+    /// Email a digest of the advisories through this SMTP server when the check fails
+    #[arg(long)]
+    pub(crate) smtp_host: Option<String>,
+    /// The SMTP server port, defaults to the standard port for --smtp-tls
+    #[arg(long)]
+    pub(crate) smtp_port: Option<u16>,
+    /// How the connection to the SMTP server is secured
+    #[arg(long, value_enum)]
+    pub(crate) smtp_tls: Option<SmtpTls>,
+    /// The SMTP username
+    #[arg(long)]
+    pub(crate) smtp_username: Option<String>,
+    /// The SMTP password
+    #[arg(long)]
+    pub(crate) smtp_password: Option<String>,
+    /// The email sender, i.e. "audit-check <audit@example.com>"
+    #[arg(long)]
+    pub(crate) email_from: Option<String>,
+    /// The comma separated email recipients
+    #[arg(long)]
+    pub(crate) email_to: Option<String>,
+    /// Kill cargo audit if it has not finished after this many seconds, 0 disables the timeout
+    #[arg(long, value_name = "SECONDS")]
+    pub(crate) timeout: Option<u64>,
+    /// Install cargo audit when it is missing or too old: false, true (latest) or a version
+    #[arg(long, value_name = "VERSION")]
+    pub(crate) install: Option<Install>,
+    /// What to do when a vulnerability is found
+    #[arg(long, value_enum)]
+    pub(crate) vulnerability_policy: Option<PolicyArg>,
+    /// What to do when unsound code is found
+    #[arg(long, value_enum)]
+    pub(crate) unsound_policy: Option<PolicyArg>,
+    /// What to do when an unmaintained crate is found
+    #[arg(long, value_enum)]
+    pub(crate) unmaintained_policy: Option<PolicyArg>,
+    /// What to do when a yanked crate is found
+    #[arg(long, value_enum)]
+    pub(crate) yanked_policy: Option<PolicyArg>,
+    /// What to do when an informational notice is found
+    #[arg(long, value_enum)]
+    pub(crate) notice_policy: Option<PolicyArg>,
+}
+
+impl Cli {
+    /// The policy flag for the given kind of advisory.
+    pub(crate) fn policy(&self, kind: AdvisoryKind) -> Option<Policy> {
+        match kind {
+            AdvisoryKind::Vulnerability => self.vulnerability_policy,
+            AdvisoryKind::Unsound => self.unsound_policy,
+            AdvisoryKind::Unmaintained => self.unmaintained_policy,
+            AdvisoryKind::Yanked => self.yanked_policy,
+            AdvisoryKind::Notice => self.notice_policy,
+        }
+        .map(Policy::from)
+    }
+}
+
+const ENV_HELP: &str = "Every flag falls back to the matching INPUT_* environment variable, \
+i.e. --dry-run-file falls back to INPUT_DRY_RUN_FILE. --repository falls back to GITHUB_REPOSITORY and --api-url to GITHUB_API_URL. \
+Settings not given either way are read from the config file.
+
+The policies default to fail for vulnerabilities and for the warnings matched by --deny, \
+and to warn for the remaining warnings.
+
+Exit codes: 0 no advisories, 1 advisories found, 2 invalid configuration, \
+3 rustc or cargo audit requirements not met, 4 cargo audit failed or timed out, 5 GitHub API or webhook error, \
+6 unexpected error, 7 only informational warnings found";
+
+/// The output format
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Format {
+    /// Log the advisories
+    #[default]
+    Text,
+    /// Log the advisories and write them to stdout as JSON
+    Json,
+}
+
+/// A policy on the command line, or in an `INPUT_*_POLICY`, which keeps clap
+/// out of the public [`Policy`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+pub(crate) enum PolicyArg {
+    /// Fail the check
+    Fail,
+    /// Report the advisory without failing the check
+    Warn,
+    /// Drop the advisory
+    Ignore,
+}
+
+impl From<PolicyArg> for Policy {
+    fn from(policy: PolicyArg) -> Self {
+        match policy {
+            PolicyArg::Fail => Self::Fail,
+            PolicyArg::Warn => Self::Warn,
+            PolicyArg::Ignore => Self::Ignore,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Cli, Format};
+    use crate::{advisory::AdvisoryKind, check::install::Install, policy::Policy};
+    use clap::{CommandFactory, Parser};
+    use tracing::Level;
+
+    #[test]
+    fn cli_is_valid() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn flags_parse() {
+        let cli = Cli::try_parse_from([
+            "audit-check",
+            "--create-issue",
+            "--dry-run",
+            "false",
+            "--level",
+            "debug",
+            "--format",
+            "json",
+            "--unmaintained-policy",
+            "ignore",
+            "--timeout",
+            "300",
+            "--install",
+            "0.21.2",
+        ])
+        .expect("valid arguments");
+        assert_eq!(Some(true), cli.create_issue);
+        assert_eq!(Some(false), cli.dry_run);
+        assert_eq!(Some(Level::DEBUG), cli.level);
+        assert_eq!(Some(Format::Json), cli.format);
+        assert_eq!(Some(Policy::Ignore), cli.policy(AdvisoryKind::Unmaintained));
+        assert_eq!(Some(300), cli.timeout);
+        assert_eq!(
+            Some(Install::Version(rustc_version::Version::new(0, 21, 2))),
+            cli.install
+        );
+        assert!(cli.policy(AdvisoryKind::Vulnerability).is_none());
+        assert!(cli.token.is_none());
+    }
+}
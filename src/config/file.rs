@@ -0,0 +1,272 @@
+// Copyright (c) 2023 audit-check developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or https://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use super::ignore::Ignore;
+use crate::{
+    advisory::AdvisoryKind, check::install::Install, cli::Format, email::SmtpTls,
+    error::AuditCheckError, policy::Policy, webhook::WebhookFormat,
+};
+use anyhow::Result;
+use serde::Deserialize;
+use std::{fs, path::Path, str::FromStr};
+use tracing::Level;
+
+/// The config file read when no other path is configured.
+pub(crate) const DEFAULT_CONFIG_FILE: &str = ".github/audit-check.toml";
+
+/// The optional TOML config file.
+///
+/// Secrets and the repository are deliberately not configurable here, they
+/// always come from the command line or the environment.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ConfigFile {
+    pub(crate) deny: Option<String>,
+    level: Option<String>,
+    pub(crate) create_issue: Option<bool>,
+    pub(crate) dry_run: Option<bool>,
+    pub(crate) dry_run_file: Option<String>,
+    pub(crate) issue_title_template: Option<String>,
+    pub(crate) issue_body_template: Option<String>,
+    pub(crate) issue_title_template_file: Option<String>,
+    pub(crate) issue_body_template_file: Option<String>,
+    pub(crate) format: Option<Format>,
+    pub(crate) report_file: Option<String>,
+    pub(crate) junit_file: Option<String>,
+    pub(crate) sbom_file: Option<String>,
+    pub(crate) osv_file: Option<String>,
+    pub(crate) gitlab_file: Option<String>,
+    pub(crate) html_report: Option<String>,
+    pub(crate) webhook_format: Option<WebhookFormat>,
+    pub(crate) webhook_state_file: Option<String>,
+    pub(crate) smtp_host: Option<String>,
+    pub(crate) smtp_port: Option<u16>,
+    pub(crate) smtp_tls: Option<SmtpTls>,
+    pub(crate) email_from: Option<String>,
+    pub(crate) email_to: Option<String>,
+    pub(crate) timeout: Option<u64>,
+    pub(crate) install: Option<Install>,
+    #[serde(default)]
+    pub(crate) policy: FilePolicies,
+    #[serde(default)]
+    pub(crate) ignore: Vec<Ignore>,
+}
+
+/// The `[policy]` table, a policy per kind of advisory.
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct FilePolicies {
+    vulnerability: Option<Policy>,
+    unsound: Option<Policy>,
+    unmaintained: Option<Policy>,
+    yanked: Option<Policy>,
+    notice: Option<Policy>,
+}
+
+impl FilePolicies {
+    pub(crate) fn get(self, kind: AdvisoryKind) -> Option<Policy> {
+        match kind {
+            AdvisoryKind::Vulnerability => self.vulnerability,
+            AdvisoryKind::Unsound => self.unsound,
+            AdvisoryKind::Unmaintained => self.unmaintained,
+            AdvisoryKind::Yanked => self.yanked,
+            AdvisoryKind::Notice => self.notice,
+        }
+    }
+}
+
+impl ConfigFile {
+    pub(crate) fn load<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path).map_err(|e| config_error(path, &e))?;
+        Self::parse(&contents, path)
+    }
+
+    pub(crate) fn parse(contents: &str, path: &Path) -> Result<Self> {
+        let mut config_file: Self = toml::from_str(contents).map_err(|e| config_error(path, &e))?;
+        config_file.validate(path)?;
+        for ignore in &mut config_file.ignore {
+            ignore.source = path.display().to_string();
+        }
+        Ok(config_file)
+    }
+
+    pub(crate) fn level(&self) -> Option<Level> {
+        // validated on load
+        self.level
+            .as_deref()
+            .and_then(|level| Level::from_str(level).ok())
+    }
+
+    fn validate(&self, path: &Path) -> Result<()> {
+        if let Some(level) = &self.level {
+            let _level = Level::from_str(level)
+                .map_err(|e| config_error(path, &format!("`level`: {e} '{level}'")))?;
+        }
+        for (idx, ignore) in self.ignore.iter().enumerate() {
+            if !is_advisory_id(&ignore.id) {
+                return Err(config_error(
+                    path,
+                    &format!(
+                        "`ignore[{idx}].id`: '{}' is not an advisory id, i.e. RUSTSEC-2020-0099 or an alias like CVE-2020-36206 or GHSA-5wg8-7c9q-794v",
+                        ignore.id
+                    ),
+                ));
+            }
+            if ignore.reason.trim().is_empty() {
+                return Err(config_error(
+                    path,
+                    &format!("`ignore[{idx}].reason`: a reason is required"),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+// RUSTSEC-YYYY-NNNN, or one of the aliases cargo audit accepts as well,
+// CVE-YYYY-NNNN (four or more digits) and GHSA-xxxx-xxxx-xxxx
+fn is_advisory_id(id: &str) -> bool {
+    let digits = |part: &str| part.chars().all(|c| c.is_ascii_digit());
+    let parts: Vec<&str> = id.split('-').collect();
+    match parts.as_slice() {
+        ["RUSTSEC", year, number] => {
+            year.len() == 4 && number.len() == 4 && digits(year) && digits(number)
+        }
+        ["CVE", year, number] => {
+            year.len() == 4 && number.len() >= 4 && digits(year) && digits(number)
+        }
+        ["GHSA", parts @ ..] => {
+            parts.len() == 3
+                && parts.iter().all(|part| {
+                    part.len() == 4
+                        && part
+                            .chars()
+                            .all(|c| c.is_ascii_digit() || c.is_ascii_lowercase())
+                })
+        }
+        _ => false,
+    }
+}
+
+fn config_error<E>(path: &Path, e: &E) -> anyhow::Error
+where
+    E: ToString + ?Sized,
+{
+    AuditCheckError::ConfigFile {
+        path: path.display().to_string(),
+        message: e.to_string().trim_end().to_string(),
+    }
+    .into()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ConfigFile, is_advisory_id};
+    use crate::{
+        advisory::AdvisoryKind, cli::Format, config::Ignore, error::AuditCheckError, policy::Policy,
+    };
+    use anyhow::Result;
+    use std::path::Path;
+    use tracing::Level;
+
+    const PATH: &str = ".github/audit-check.toml";
+
+    fn error_message(result: Result<ConfigFile>) -> String {
+        match result.err().map(anyhow::Error::downcast::<AuditCheckError>) {
+            Some(Ok(AuditCheckError::ConfigFile { message, .. })) => message,
+            _ => String::new(),
+        }
+    }
+
+    #[test]
+    fn parse_works() -> Result<()> {
+        let config_file = ConfigFile::parse(
+            r#"
+deny = "unsound"
+level = "debug"
+create_issue = true
+format = "json"
+
+[policy]
+unmaintained = "ignore"
+
+[[ignore]]
+id = "RUSTSEC-2020-0099"
+reason = "aovec is only used in tests"
+"#,
+            Path::new(PATH),
+        )?;
+        assert_eq!(Some("unsound"), config_file.deny.as_deref());
+        assert_eq!(Some(Level::DEBUG), config_file.level());
+        assert_eq!(Some(true), config_file.create_issue);
+        assert_eq!(Some(Format::Json), config_file.format);
+        assert_eq!(
+            Some(Policy::Ignore),
+            config_file.policy.get(AdvisoryKind::Unmaintained)
+        );
+        assert!(config_file.policy.get(AdvisoryKind::Unsound).is_none());
+        assert_eq!(
+            vec![Ignore {
+                id: "RUSTSEC-2020-0099".to_string(),
+                reason: "aovec is only used in tests".to_string(),
+                source: PATH.to_string(),
+            }],
+            config_file.ignore
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_keys_are_reported() {
+        let message = error_message(ConfigFile::parse("dney = \"warnings\"", Path::new(PATH)));
+        assert!(message.contains("dney"));
+        assert!(message.contains("line 1"));
+    }
+
+    #[test]
+    fn invalid_values_are_reported() {
+        let message = error_message(ConfigFile::parse("create_issue = \"yes\"", Path::new(PATH)));
+        assert!(message.contains("create_issue"));
+        let message = error_message(ConfigFile::parse("level = \"LOUD\"", Path::new(PATH)));
+        assert!(message.starts_with("`level`"));
+        let message = error_message(ConfigFile::parse(
+            "[policy]\nunmaintained = \"skip\"",
+            Path::new(PATH),
+        ));
+        assert!(message.contains("skip"));
+    }
+
+    #[test]
+    fn invalid_ignores_are_reported() {
+        let message = error_message(ConfigFile::parse(
+            "[[ignore]]\nid = \"RUSTSEC-2020-0099\"\nreason = \"\"\n\n[[ignore]]\nid = \"CVE-2020-1\"\nreason = \"a\"",
+            Path::new(PATH),
+        ));
+        assert!(message.starts_with("`ignore[0].reason`"));
+        let message = error_message(ConfigFile::parse(
+            "[[ignore]]\nid = \"CVE-2020-1\"\nreason = \"a\"",
+            Path::new(PATH),
+        ));
+        assert!(message.starts_with("`ignore[0].id`"));
+    }
+
+    #[test]
+    fn is_advisory_id_works() {
+        assert!(is_advisory_id("RUSTSEC-2020-0099"));
+        assert!(!is_advisory_id("RUSTSEC-2020-099"));
+        assert!(!is_advisory_id("RUSTSEC-2020-0099-1"));
+        assert!(is_advisory_id("GHSA-5wg8-7c9q-794v"));
+        assert!(!is_advisory_id("GHSA-5wg8-7c9q"));
+        assert!(is_advisory_id("CVE-2020-36206"));
+        assert!(!is_advisory_id("CVE-2020-362"));
+    }
+}
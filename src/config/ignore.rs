@@ -0,0 +1,209 @@
+// Copyright (c) 2023 audit-check developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or https://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use anyhow::Result;
+use serde::Deserialize;
+use std::{fs, path::Path};
+use tracing::{debug, warn};
+
+/// The `cargo audit` config file.
+pub(crate) const AUDIT_TOML: &str = ".cargo/audit.toml";
+/// The `cargo deny` config files, in the order `cargo deny` looks for them.
+pub(crate) const DENY_TOMLS: [&str; 3] = ["deny.toml", ".deny.toml", ".cargo/deny.toml"];
+
+/// An advisory that should not fail the check.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(deny_unknown_fields)]
+#[non_exhaustive]
+pub struct Ignore {
+    /// The advisory id, i.e. `RUSTSEC-2020-0099`, or one of its aliases
+    pub id: String,
+    /// Why the advisory is ignored
+    pub reason: String,
+    /// The file the ignore was read from.
+    #[serde(skip)]
+    pub source: String,
+}
+
+impl Ignore {
+    /// Ignore the advisory with the given id, the source is named in the
+    /// reason of the decision.
+    #[must_use]
+    pub fn new(id: &str, reason: &str, source: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            reason: reason.to_string(),
+            source: source.to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+struct ToolConfig {
+    #[serde(default)]
+    advisories: Advisories,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+struct Advisories {
+    #[serde(default)]
+    ignore: Vec<IgnoreEntry>,
+}
+
+// `cargo audit` only supports ids, `cargo deny` also supports tables with
+// an optional reason, and tables ignoring crates rather than advisories.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+enum IgnoreEntry {
+    Id(String),
+    Table {
+        id: Option<String>,
+        reason: Option<String>,
+    },
+}
+
+/// Collect the ignored advisories from `.cargo/audit.toml` and the first
+/// `deny.toml` found.
+///
+/// These files belong to other tools, so a file that cannot be read is
+/// reported and skipped rather than failing the check.
+pub(crate) fn discover() -> Vec<Ignore> {
+    let deny_toml = DENY_TOMLS.iter().find(|path| Path::new(path).is_file());
+    [Some(&AUDIT_TOML), deny_toml]
+        .into_iter()
+        .flatten()
+        .filter(|path| Path::new(path).is_file())
+        .flat_map(|path| match load(path) {
+            Ok(ignores) => {
+                debug!("{} ignored advisories read from '{path}'", ignores.len());
+                ignores
+            }
+            Err(e) => {
+                warn!("Unable to read the ignored advisories from '{path}': {e}");
+                vec![]
+            }
+        })
+        .collect()
+}
+
+/// The `cargo audit` config without its ignored advisories, or `None` when
+/// it ignores none.
+///
+/// `cargo audit` drops the advisories it ignores from its report, so it is
+/// run with this copy and the ignores are applied, and attributed to
+/// [`AUDIT_TOML`], along with the others.
+pub(crate) fn without_ignores(contents: &str) -> Result<Option<String>> {
+    let mut config: toml::Table = toml::from_str(contents)?;
+    let ignored = config
+        .get_mut("advisories")
+        .and_then(toml::Value::as_table_mut)
+        .and_then(|advisories| advisories.remove("ignore"));
+    Ok(match ignored {
+        Some(_ignored) => Some(toml::to_string(&config)?),
+        None => None,
+    })
+}
+
+fn load(path: &str) -> Result<Vec<Ignore>> {
+    parse(&fs::read_to_string(path)?, path)
+}
+
+pub(crate) fn parse(contents: &str, source: &str) -> Result<Vec<Ignore>> {
+    let tool_config: ToolConfig = toml::from_str(contents)?;
+    Ok(tool_config
+        .advisories
+        .ignore
+        .into_iter()
+        .filter_map(|entry| {
+            let (id, reason) = match entry {
+                IgnoreEntry::Id(id) => (id, None),
+                IgnoreEntry::Table { id, reason } => (id?, reason),
+            };
+            Some(Ignore {
+                id,
+                reason: reason.unwrap_or_else(|| "no reason given".to_string()),
+                source: source.to_string(),
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{AUDIT_TOML, Ignore, parse, without_ignores};
+    use anyhow::Result;
+
+    #[test]
+    fn parse_audit_toml_works() -> Result<()> {
+        let ignores = parse(
+            r#"
+[advisories]
+ignore = ["RUSTSEC-2020-0099"]
+informational_warnings = ["unmaintained"]
+
+[output]
+deny = ["unmaintained"]
+"#,
+            AUDIT_TOML,
+        )?;
+        assert_eq!(
+            vec![Ignore {
+                id: "RUSTSEC-2020-0099".to_string(),
+                reason: "no reason given".to_string(),
+                source: AUDIT_TOML.to_string(),
+            }],
+            ignores
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parse_deny_toml_works() -> Result<()> {
+        let ignores = parse(
+            r#"
+[graph]
+all-features = true
+
+[advisories]
+version = 2
+ignore = [
+    "RUSTSEC-2021-0065",
+    { id = "RUSTSEC-2022-0040", reason = "owning_ref is not used at runtime" },
+    { crate = "smallvec@0.4.5", reason = "pinned by aovec" },
+]
+
+[bans]
+multiple-versions = "warn"
+"#,
+            "deny.toml",
+        )?;
+        assert_eq!(2, ignores.len());
+        assert_eq!("RUSTSEC-2021-0065", ignores[0].id);
+        assert_eq!("RUSTSEC-2022-0040", ignores[1].id);
+        assert_eq!("owning_ref is not used at runtime", ignores[1].reason);
+        assert_eq!("deny.toml", ignores[1].source);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_without_advisories_works() -> Result<()> {
+        assert!(parse("[bans]\nmultiple-versions = \"deny\"", "deny.toml")?.is_empty());
+        Ok(())
+    }
+    #[test]
+    fn without_ignores_works() -> Result<()> {
+        let stripped = without_ignores(
+            "[advisories]\nignore = [\"RUSTSEC-2020-0099\"]\n\n[output]\ndeny = [\"unmaintained\"]\n",
+        )?
+        .unwrap_or_default();
+        assert!(!stripped.contains("RUSTSEC-2020-0099"));
+        assert!(stripped.contains("unmaintained"));
+        assert_eq!(None, without_ignores("[output]\nquiet = true")?);
+        Ok(())
+    }
+}
@@ -0,0 +1,873 @@
+// Copyright (c) 2023 audit-check developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or https://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use crate::{
+    advisory::AdvisoryKind,
+    check::install::Install,
+    cli::{Cli, Format, PolicyArg},
+    email::SmtpTls,
+    error::AuditCheckError,
+    policy::{Policies, Policy},
+    template,
+    webhook::WebhookFormat,
+};
+use anyhow::Result;
+use clap::ValueEnum;
+use lettre::message::Mailbox;
+use std::{env, fmt::Display, fs, path::Path, str::FromStr, time::Duration};
+use tracing::Level;
+
+use self::file::{ConfigFile, DEFAULT_CONFIG_FILE, FilePolicies};
+pub use self::ignore::Ignore;
+
+mod file;
+pub(crate) mod ignore;
+
+#[derive(Clone, Debug)]
+pub(crate) struct Config {
+    pub(crate) token: Option<String>,
+    pub(crate) deny: String,
+    pub(crate) level: Level,
+    pub(crate) owner_repo: Option<String>,
+    /// The GitHub API, i.e. `https://api.github.com`
+    pub(crate) api_url: String,
+    pub(crate) create_issue: bool,
+    pub(crate) dry_run: bool,
+    pub(crate) dry_run_file: String,
+    pub(crate) title_template: Option<String>,
+    pub(crate) body_template: Option<String>,
+    pub(crate) format: Format,
+    pub(crate) outputs: Outputs,
+    pub(crate) webhook: Option<Webhook>,
+    pub(crate) email: Option<Email>,
+    /// How long cargo audit may run before it is killed.
+    pub(crate) timeout: Option<Duration>,
+    /// Whether to install cargo audit when it is missing or too old.
+    pub(crate) install: Install,
+    pub(crate) policies: Policies,
+    pub(crate) ignore: Vec<Ignore>,
+}
+
+/// The files reports are written to, every report is optional.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Outputs {
+    /// The versioned JSON report
+    pub(crate) report_file: Option<String>,
+    /// The `JUnit` XML report
+    pub(crate) junit_file: Option<String>,
+    /// The `CycloneDX` SBOM
+    pub(crate) sbom_file: Option<String>,
+    /// The OSV export of the advisories
+    pub(crate) osv_file: Option<String>,
+    /// The GitLab dependency scanning report
+    pub(crate) gitlab_file: Option<String>,
+    /// The standalone HTML report
+    pub(crate) html_report: Option<String>,
+}
+
+impl Outputs {
+    fn resolve<F>(cli: &Cli, lookup: F, file: &ConfigFile) -> Self
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let output = |flag: &Option<String>, input: &str, file: &Option<String>| {
+            flag.clone()
+                .or_else(|| lookup(input))
+                .or_else(|| file.clone())
+        };
+        Self {
+            report_file: output(&cli.report_file, "INPUT_REPORT_FILE", &file.report_file),
+            junit_file: output(&cli.junit_file, "INPUT_JUNIT_FILE", &file.junit_file),
+            sbom_file: output(&cli.sbom_file, "INPUT_SBOM_FILE", &file.sbom_file),
+            osv_file: output(&cli.osv_file, "INPUT_OSV_FILE", &file.osv_file),
+            gitlab_file: output(&cli.gitlab_file, "INPUT_GITLAB_FILE", &file.gitlab_file),
+            html_report: output(&cli.html_report, "INPUT_HTML_REPORT", &file.html_report),
+        }
+    }
+}
+
+/// The webhook advisory changes are posted to.
+#[derive(Clone, Debug)]
+pub(crate) struct Webhook {
+    pub(crate) url: String,
+    pub(crate) format: WebhookFormat,
+    /// The advisories last posted, to only post changes
+    pub(crate) state_file: String,
+}
+
+impl Webhook {
+    // There is no webhook without a URL, which is a secret and so is not
+    // read from the config file.
+    fn resolve<F>(cli: &Cli, lookup: F, file: &ConfigFile) -> Result<Option<Self>>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let Some(url) = cli
+            .webhook_url
+            .clone()
+            .or_else(|| lookup("INPUT_WEBHOOK_URL"))
+        else {
+            return Ok(None);
+        };
+        let format = value_enum_or(
+            cli.webhook_format,
+            &lookup,
+            "INPUT_WEBHOOK_FORMAT",
+            file.webhook_format,
+        )?;
+        let state_file = cli
+            .webhook_state_file
+            .clone()
+            .or_else(|| lookup("INPUT_WEBHOOK_STATE_FILE"))
+            .or_else(|| file.webhook_state_file.clone())
+            .unwrap_or_else(|| ".audit-check-state.json".to_string());
+        Ok(Some(Self {
+            url,
+            format,
+            state_file,
+        }))
+    }
+}
+
+/// The SMTP server and addresses the email digest is sent with.
+#[derive(Clone, Debug)]
+pub(crate) struct Email {
+    pub(crate) host: String,
+    pub(crate) port: u16,
+    pub(crate) tls: SmtpTls,
+    pub(crate) username: Option<String>,
+    pub(crate) password: Option<String>,
+    pub(crate) from: Mailbox,
+    pub(crate) to: Vec<Mailbox>,
+}
+
+impl Email {
+    // There is no email without an SMTP host. The credentials are secrets and
+    // so are not read from the config file.
+    fn resolve<F>(cli: &Cli, lookup: F, file: &ConfigFile) -> Result<Option<Self>>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let setting = |flag: &Option<String>, input: &str, file: &Option<String>| {
+            flag.clone()
+                .or_else(|| lookup(input))
+                .or_else(|| file.clone())
+        };
+        let Some(host) = setting(&cli.smtp_host, "INPUT_SMTP_HOST", &file.smtp_host) else {
+            return Ok(None);
+        };
+        let tls = value_enum_or(cli.smtp_tls, &lookup, "INPUT_SMTP_TLS", file.smtp_tls)?;
+        let port = parse_or(
+            cli.smtp_port,
+            &lookup,
+            "INPUT_SMTP_PORT",
+            file.smtp_port.unwrap_or(tls.default_port()),
+        )?;
+        let username = cli
+            .smtp_username
+            .clone()
+            .or_else(|| lookup("INPUT_SMTP_USERNAME"));
+        let password = cli
+            .smtp_password
+            .clone()
+            .or_else(|| lookup("INPUT_SMTP_PASSWORD"));
+        if username.is_some() && password.is_none() {
+            return Err(AuditCheckError::MissingInput {
+                input: "smtp_password",
+            }
+            .into());
+        }
+        let from = setting(&cli.email_from, "INPUT_EMAIL_FROM", &file.email_from).ok_or(
+            AuditCheckError::MissingInput {
+                input: "email_from",
+            },
+        )?;
+        let to = setting(&cli.email_to, "INPUT_EMAIL_TO", &file.email_to)
+            .ok_or(AuditCheckError::MissingInput { input: "email_to" })?;
+        Ok(Some(Self {
+            host,
+            port,
+            tls,
+            username,
+            password,
+            from: mailbox(&from, "INPUT_EMAIL_FROM")?,
+            to: to
+                .split(',')
+                .map(|to| mailbox(to, "INPUT_EMAIL_TO"))
+                .collect::<Result<_>>()?,
+        }))
+    }
+}
+
+impl Config {
+    /// Command line flags take precedence over the `INPUT_*` environment
+    /// variables, which take precedence over the config file.
+    pub(crate) fn from_cli(cli: Cli) -> Result<Self> {
+        // Actions set unset inputs to the empty string
+        Self::from_lookup(cli, |name| {
+            env::var(name).ok().filter(|value| !value.is_empty())
+        })
+    }
+
+    fn from_lookup<F>(cli: Cli, lookup: F) -> Result<Self>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let file = match cli.config.clone().or_else(|| lookup("INPUT_CONFIG")) {
+            Some(path) => ConfigFile::load(path)?,
+            None if Path::new(DEFAULT_CONFIG_FILE).is_file() => {
+                ConfigFile::load(DEFAULT_CONFIG_FILE)?
+            }
+            None => ConfigFile::default(),
+        };
+        let mut config = Self::resolve(cli, lookup, file)?;
+        config.ignore.extend(ignore::discover());
+        Ok(config)
+    }
+
+    fn resolve<F>(cli: Cli, lookup: F, file: ConfigFile) -> Result<Self>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let policy_flags = AdvisoryKind::ALL.map(|kind| cli.policy(kind));
+        let outputs = Outputs::resolve(&cli, &lookup, &file);
+        let webhook = Webhook::resolve(&cli, &lookup, &file)?;
+        let email = Email::resolve(&cli, &lookup, &file)?;
+        let token = cli.token.or_else(|| lookup("INPUT_TOKEN"));
+        let owner_repo = cli.repository.or_else(|| lookup("GITHUB_REPOSITORY"));
+        let api_url = cli
+            .api_url
+            .or_else(|| lookup("GITHUB_API_URL"))
+            .unwrap_or_else(|| "https://api.github.com".to_string());
+        let level = parse_or(
+            cli.level,
+            &lookup,
+            "INPUT_LEVEL",
+            file.level().unwrap_or(Level::INFO),
+        )?;
+        let create_issue = parse_or(
+            cli.create_issue,
+            &lookup,
+            "INPUT_CREATE_ISSUE",
+            file.create_issue.unwrap_or(false),
+        )?;
+        let dry_run = parse_or(
+            cli.dry_run,
+            &lookup,
+            "INPUT_DRY_RUN",
+            file.dry_run.unwrap_or(false),
+        )?;
+        let deny = cli
+            .deny
+            .or_else(|| lookup("INPUT_DENY"))
+            .or(file.deny)
+            .unwrap_or_else(|| "warnings".to_string());
+        let dry_run_file = cli
+            .dry_run_file
+            .or_else(|| lookup("INPUT_DRY_RUN_FILE"))
+            .or(file.dry_run_file)
+            .unwrap_or_else(|| "audit-check-issue.json".to_string());
+        let title_template = input_template(
+            cli.issue_title_template
+                .or_else(|| lookup("INPUT_ISSUE_TITLE_TEMPLATE"))
+                .or(file.issue_title_template),
+            cli.issue_title_template_file
+                .or_else(|| lookup("INPUT_ISSUE_TITLE_TEMPLATE_FILE"))
+                .or(file.issue_title_template_file),
+            "INPUT_ISSUE_TITLE_TEMPLATE_FILE",
+            "issue_title_template",
+        )?;
+        let body_template = input_template(
+            cli.issue_body_template
+                .or_else(|| lookup("INPUT_ISSUE_BODY_TEMPLATE"))
+                .or(file.issue_body_template),
+            cli.issue_body_template_file
+                .or_else(|| lookup("INPUT_ISSUE_BODY_TEMPLATE_FILE"))
+                .or(file.issue_body_template_file),
+            "INPUT_ISSUE_BODY_TEMPLATE_FILE",
+            "issue_body_template",
+        )?;
+        let format = value_enum_or(cli.format, &lookup, "INPUT_FORMAT", file.format)?;
+        // 0 disables the timeout
+        let timeout = parse_or(
+            cli.timeout,
+            &lookup,
+            "INPUT_TIMEOUT",
+            file.timeout.unwrap_or(0),
+        )?;
+        let timeout = (timeout > 0).then(|| Duration::from_secs(timeout));
+        let install = parse_or(
+            cli.install,
+            &lookup,
+            "INPUT_INSTALL",
+            file.install.unwrap_or_default(),
+        )?;
+        let policies = resolve_policies(policy_flags, &lookup, file.policy, &deny)?;
+        let ignore = file.ignore;
+
+        let config = Self {
+            token,
+            deny,
+            level,
+            owner_repo,
+            api_url,
+            create_issue,
+            dry_run,
+            dry_run_file,
+            title_template,
+            body_template,
+            format,
+            outputs,
+            webhook,
+            email,
+            timeout,
+            install,
+            policies,
+            ignore,
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Whether this run will make requests that write to GitHub.
+    pub(crate) fn writes_to_github(&self) -> bool {
+        self.create_issue && !self.dry_run
+    }
+
+    // The token and repository are only required when a feature that needs
+    // them is enabled.
+    fn validate(&self) -> Result<()> {
+        if self.writes_to_github() {
+            if self.token.is_none() {
+                return Err(AuditCheckError::MissingInput { input: "token" }.into());
+            }
+            if self.owner_repo.is_none() {
+                return Err(AuditCheckError::MissingInput {
+                    input: "GITHUB_REPOSITORY",
+                }
+                .into());
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_or<T, F>(flag: Option<T>, lookup: F, input: &'static str, default: T) -> Result<T>
+where
+    T: FromStr,
+    T::Err: Display,
+    F: Fn(&str) -> Option<String>,
+{
+    match flag {
+        Some(value) => Ok(value),
+        None => lookup(input)
+            .map(|value| {
+                value
+                    .parse::<T>()
+                    .map_err(|e| AuditCheckError::InvalidInput {
+                        input,
+                        reason: format!("'{value}' {e}"),
+                    })
+            })
+            .transpose()
+            .map(|value| value.unwrap_or(default))
+            .map_err(Into::into),
+    }
+}
+
+// Like `parse_or`, for the inputs matched case-insensitively against a `ValueEnum`.
+fn value_enum_or<T, F>(
+    flag: Option<T>,
+    lookup: F,
+    input: &'static str,
+    file: Option<T>,
+) -> Result<T>
+where
+    T: ValueEnum + Default,
+    F: Fn(&str) -> Option<String>,
+{
+    match flag {
+        Some(value) => Ok(value),
+        None => Ok(lookup(input)
+            .map(|value| {
+                T::from_str(&value, true)
+                    .map_err(|reason| AuditCheckError::InvalidInput { input, reason })
+            })
+            .transpose()?
+            .or(file)
+            .unwrap_or_default()),
+    }
+}
+
+// i.e. `audit@example.com` or `audit-check <audit@example.com>`
+fn mailbox(address: &str, input: &'static str) -> Result<Mailbox> {
+    address.trim().parse().map_err(|e| {
+        AuditCheckError::InvalidInput {
+            input,
+            reason: format!("'{}' {e}", address.trim()),
+        }
+        .into()
+    })
+}
+
+// Each policy falls back to the `deny` input when not set.
+fn resolve_policies<F>(
+    flags: [Option<Policy>; 5],
+    lookup: F,
+    file: FilePolicies,
+    deny: &str,
+) -> Result<Policies>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut policies = Policies::from_deny(deny);
+    for (kind, flag) in AdvisoryKind::ALL.into_iter().zip(flags) {
+        let policy = match flag {
+            Some(policy) => Some(policy),
+            None => lookup(policy_input(kind))
+                .map(|policy| {
+                    PolicyArg::from_str(&policy, true)
+                        .map(Policy::from)
+                        .map_err(|reason| AuditCheckError::InvalidInput {
+                            input: policy_input(kind),
+                            reason,
+                        })
+                })
+                .transpose()?
+                .or(file.get(kind)),
+        };
+        if let Some(policy) = policy {
+            policies.set(kind, policy);
+        }
+    }
+    Ok(policies)
+}
+
+fn policy_input(kind: AdvisoryKind) -> &'static str {
+    match kind {
+        AdvisoryKind::Vulnerability => "INPUT_VULNERABILITY_POLICY",
+        AdvisoryKind::Unsound => "INPUT_UNSOUND_POLICY",
+        AdvisoryKind::Unmaintained => "INPUT_UNMAINTAINED_POLICY",
+        AdvisoryKind::Yanked => "INPUT_YANKED_POLICY",
+        AdvisoryKind::Notice => "INPUT_NOTICE_POLICY",
+    }
+}
+
+// An inline template takes precedence over a template file.
+fn input_template(
+    template: Option<String>,
+    file: Option<String>,
+    file_input: &'static str,
+    name: &'static str,
+) -> Result<Option<String>> {
+    let template = if let Some(template) = template {
+        template
+    } else if let Some(path) = file {
+        fs::read_to_string(&path).map_err(|e| AuditCheckError::InvalidInput {
+            input: file_input,
+            reason: format!("unable to read '{path}': {e}"),
+        })?
+    } else {
+        return Ok(None);
+    };
+    template::validate(name, &template)?;
+    Ok(Some(template))
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::{Config, Outputs, file::ConfigFile};
+    use crate::{
+        advisory::AdvisoryKind,
+        check::install::Install,
+        cli::{Cli, Format, PolicyArg},
+        error::AuditCheckError,
+        policy::{Policies, Policy},
+        webhook::WebhookFormat,
+    };
+    use anyhow::Result;
+    use std::{collections::HashMap, path::Path, time::Duration};
+    use tracing::Level;
+
+    /// A config with the defaults, for use in other tests.
+    pub(crate) fn config() -> Config {
+        Config {
+            token: None,
+            deny: "warnings".to_string(),
+            level: Level::INFO,
+            owner_repo: Some("rustyhorde/audit-check".to_string()),
+            api_url: "https://api.github.com".to_string(),
+            create_issue: false,
+            dry_run: true,
+            dry_run_file: "audit-check-issue.json".to_string(),
+            title_template: None,
+            body_template: None,
+            format: Format::Text,
+            outputs: Outputs::default(),
+            webhook: None,
+            email: None,
+            timeout: None,
+            install: Install::Never,
+            policies: Policies::from_deny("warnings"),
+            ignore: vec![],
+        }
+    }
+
+    fn lookup(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| ((*k).to_string(), (*v).to_string()))
+            .collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    fn missing_input(config: Result<Config>) -> Option<&'static str> {
+        match config.err()?.downcast::<AuditCheckError>() {
+            Ok(AuditCheckError::MissingInput { input }) => Some(input),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn token_is_optional_without_github_writes() {
+        let config = Config::from_lookup(Cli::default(), lookup(&[]));
+        assert!(config.is_ok());
+        let config = config.unwrap();
+        assert!(config.token.is_none());
+        assert!(config.owner_repo.is_none());
+        assert_eq!(Level::INFO, config.level);
+        assert_eq!(Format::Text, config.format);
+        assert!(config.timeout.is_none());
+        assert_eq!(Install::Never, config.install);
+    }
+
+    #[test]
+    fn token_is_required_to_create_issues() {
+        let config = Config::from_lookup(
+            Cli::default(),
+            lookup(&[("GITHUB_REPOSITORY", "a/b"), ("INPUT_CREATE_ISSUE", "true")]),
+        );
+        assert_eq!(Some("token"), missing_input(config));
+    }
+
+    #[test]
+    fn token_is_optional_for_dry_runs() {
+        let config = Config::from_lookup(
+            Cli::default(),
+            lookup(&[
+                ("GITHUB_REPOSITORY", "a/b"),
+                ("INPUT_CREATE_ISSUE", "true"),
+                ("INPUT_DRY_RUN", "true"),
+            ]),
+        );
+        assert!(config.is_ok());
+    }
+
+    #[test]
+    fn email_requires_addresses() {
+        let config = Config::from_lookup(
+            Cli::default(),
+            lookup(&[
+                ("INPUT_SMTP_HOST", "localhost"),
+                ("INPUT_EMAIL_TO", "a@b.c"),
+            ]),
+        );
+        assert_eq!(Some("email_from"), missing_input(config));
+        let config = Config::from_lookup(
+            Cli::default(),
+            lookup(&[
+                ("INPUT_SMTP_HOST", "localhost"),
+                ("INPUT_EMAIL_FROM", "a@b.c"),
+                ("INPUT_EMAIL_TO", "a@b.c, not an address"),
+            ]),
+        );
+        assert_eq!(Some("INPUT_EMAIL_TO"), invalid_input(config));
+    }
+
+    #[test]
+    fn repository_is_required_to_create_issues() {
+        let config = Config::from_lookup(
+            Cli::default(),
+            lookup(&[("INPUT_TOKEN", "abc"), ("INPUT_CREATE_ISSUE", "true")]),
+        );
+        assert_eq!(Some("GITHUB_REPOSITORY"), missing_input(config));
+    }
+
+    #[test]
+    fn flags_take_precedence() {
+        let cli = Cli {
+            deny: Some("unsound".to_string()),
+            level: Some(Level::TRACE),
+            create_issue: Some(false),
+            format: Some(Format::Json),
+            ..Cli::default()
+        };
+        let config = Config::from_lookup(
+            cli,
+            lookup(&[
+                ("INPUT_DENY", "warnings"),
+                ("INPUT_LEVEL", "ERROR"),
+                ("INPUT_CREATE_ISSUE", "true"),
+                ("INPUT_FORMAT", "text"),
+            ]),
+        );
+        assert!(config.is_ok());
+        let config = config.unwrap();
+        assert_eq!("unsound", config.deny);
+        assert_eq!(Level::TRACE, config.level);
+        assert!(!config.create_issue);
+        assert_eq!(Format::Json, config.format);
+    }
+
+    #[test]
+    fn config_file_has_lowest_precedence() -> Result<()> {
+        let file = ConfigFile::parse(
+            r#"
+deny = "unsound"
+level = "debug"
+dry_run = true
+format = "json"
+timeout = 120
+install = true
+report_file = "audit-check.json"
+junit_file = "audit-check.xml"
+sbom_file = "sbom.cdx.json"
+osv_file = "osv.json"
+gitlab_file = "gl-dependency-scanning-report.json"
+html_report = "audit-check.html"
+webhook_format = "slack"
+smtp_host = "smtp.example.com"
+smtp_tls = "tls"
+email_from = "audit@example.com"
+email_to = "security@example.com, ops@example.com"
+
+[[ignore]]
+id = "RUSTSEC-2020-0099"
+reason = "aovec is only used in tests"
+"#,
+            Path::new(".github/audit-check.toml"),
+        )?;
+        let cli = Cli {
+            level: Some(Level::TRACE),
+            ..Cli::default()
+        };
+        let config = Config::resolve(
+            cli,
+            lookup(&[
+                ("INPUT_FORMAT", "text"),
+                (
+                    "INPUT_WEBHOOK_URL",
+                    "https://hooks.slack.com/services/T0/B0/X",
+                ),
+                ("INPUT_EMAIL_TO", "security@example.com"),
+            ]),
+            file,
+        )?;
+        assert_eq!(Some(Duration::from_mins(2)), config.timeout);
+        assert_eq!(Install::Latest, config.install);
+        assert_eq!(
+            Some("audit-check.json"),
+            config.outputs.report_file.as_deref()
+        );
+        assert_eq!(
+            Some("audit-check.xml"),
+            config.outputs.junit_file.as_deref()
+        );
+        assert_eq!(Some("sbom.cdx.json"), config.outputs.sbom_file.as_deref());
+        assert_eq!(Some("osv.json"), config.outputs.osv_file.as_deref());
+        assert_eq!(
+            Some("gl-dependency-scanning-report.json"),
+            config.outputs.gitlab_file.as_deref()
+        );
+        assert_eq!(
+            Some("audit-check.html"),
+            config.outputs.html_report.as_deref()
+        );
+        let webhook = config.webhook.as_ref();
+        assert_eq!(
+            Some(WebhookFormat::Slack),
+            webhook.map(|webhook| webhook.format)
+        );
+        assert_eq!(
+            Some(".audit-check-state.json"),
+            webhook.map(|webhook| webhook.state_file.as_str())
+        );
+        let email = config.email.as_ref();
+        assert_eq!(Some(465), email.map(|email| email.port));
+        assert_eq!(
+            Some("security@example.com".to_string()),
+            email.map(|email| email.to.iter().map(ToString::to_string).collect())
+        );
+        assert_eq!("unsound", config.deny);
+        assert_eq!(Level::TRACE, config.level);
+        assert!(config.dry_run);
+        assert_eq!(Format::Text, config.format);
+        assert_eq!(
+            Some("aovec is only used in tests"),
+            config
+                .ignore
+                .iter()
+                .find(|ignore| ignore.id == "RUSTSEC-2020-0099")
+                .map(|ignore| ignore.reason.as_str())
+        );
+        assert!(
+            !config
+                .ignore
+                .iter()
+                .any(|ignore| ignore.id == "RUSTSEC-2022-0040")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn explicit_inputs_override_the_config_file() -> Result<()> {
+        let file =
+            || ConfigFile::parse("deny = \"unsound\"", Path::new(".github/audit-check.toml"));
+        let config = Config::resolve(Cli::default(), lookup(&[]), file()?)?;
+        assert_eq!("unsound", config.deny);
+        let config = Config::resolve(
+            Cli::default(),
+            lookup(&[("INPUT_DENY", "warnings")]),
+            file()?,
+        )?;
+        assert_eq!("warnings", config.deny);
+        Ok(())
+    }
+
+    #[test]
+    fn action_inputs_have_no_defaults() {
+        // Actions always set inputs with a default, which would hide the
+        // config file, the defaults live in `Config::resolve` instead
+        let action = include_str!("../../action.yml");
+        assert!(
+            !action
+                .lines()
+                .any(|line| line.trim().starts_with("default:"))
+        );
+    }
+
+    #[test]
+    fn policies_default_to_deny() -> Result<()> {
+        let cli = Cli {
+            deny: Some("unsound".to_string()),
+            ..Cli::default()
+        };
+        let config = Config::resolve(cli, lookup(&[]), ConfigFile::default())?;
+        assert_eq!(Policies::from_deny("unsound"), config.policies);
+        Ok(())
+    }
+
+    #[test]
+    fn policies_follow_precedence() -> Result<()> {
+        let file = ConfigFile::parse(
+            "[policy]\nunmaintained = \"fail\"\nyanked = \"fail\"\nnotice = \"ignore\"",
+            Path::new(".github/audit-check.toml"),
+        )?;
+        let cli = Cli {
+            unmaintained_policy: Some(PolicyArg::Ignore),
+            ..Cli::default()
+        };
+        let config = Config::resolve(
+            cli,
+            lookup(&[
+                ("INPUT_DENY", "unsound"),
+                ("INPUT_UNMAINTAINED_POLICY", "warn"),
+                ("INPUT_YANKED_POLICY", "warn"),
+            ]),
+            file,
+        )?;
+        let policy = |kind| config.policies.get(kind);
+        assert_eq!(Policy::Fail, policy(AdvisoryKind::Vulnerability));
+        assert_eq!(Policy::Fail, policy(AdvisoryKind::Unsound));
+        assert_eq!(Policy::Ignore, policy(AdvisoryKind::Unmaintained));
+        assert_eq!(Policy::Warn, policy(AdvisoryKind::Yanked));
+        assert_eq!(Policy::Ignore, policy(AdvisoryKind::Notice));
+        Ok(())
+    }
+
+    #[test]
+    fn missing_config_file_fails() {
+        let config = Config::from_lookup(
+            Cli::default(),
+            lookup(&[("INPUT_CONFIG", "does/not/exist.toml")]),
+        );
+        assert!(matches!(
+            config.err().map(anyhow::Error::downcast::<AuditCheckError>),
+            Some(Ok(AuditCheckError::ConfigFile { .. }))
+        ));
+    }
+
+    fn invalid_input(config: Result<Config>) -> Option<&'static str> {
+        match config.err()?.downcast::<AuditCheckError>() {
+            Ok(AuditCheckError::InvalidInput { input, .. }) => Some(input),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn invalid_inputs_fail() {
+        let config = Config::from_lookup(Cli::default(), lookup(&[("INPUT_DRY_RUN", "yes")]));
+        assert_eq!(Some("INPUT_DRY_RUN"), invalid_input(config));
+        let config = Config::from_lookup(Cli::default(), lookup(&[("INPUT_FORMAT", "xml")]));
+        assert_eq!(Some("INPUT_FORMAT"), invalid_input(config));
+        let config =
+            Config::from_lookup(Cli::default(), lookup(&[("INPUT_UNSOUND_POLICY", "skip")]));
+        assert_eq!(Some("INPUT_UNSOUND_POLICY"), invalid_input(config));
+        let config = Config::from_lookup(Cli::default(), lookup(&[("INPUT_TIMEOUT", "5m")]));
+        assert_eq!(Some("INPUT_TIMEOUT"), invalid_input(config));
+        let config = Config::from_lookup(Cli::default(), lookup(&[("INPUT_INSTALL", "maybe")]));
+        assert_eq!(Some("INPUT_INSTALL"), invalid_input(config));
+        let config = Config::from_lookup(
+            Cli::default(),
+            lookup(&[
+                ("INPUT_WEBHOOK_URL", "https://example.com"),
+                ("INPUT_WEBHOOK_FORMAT", "discord"),
+            ]),
+        );
+        assert_eq!(Some("INPUT_WEBHOOK_FORMAT"), invalid_input(config));
+    }
+
+    #[test]
+    fn inline_template_wins() {
+        let config = Config::from_lookup(
+            Cli::default(),
+            lookup(&[
+                ("INPUT_ISSUE_TITLE_TEMPLATE", "{{ advisories | length }}"),
+                ("INPUT_ISSUE_TITLE_TEMPLATE_FILE", "does/not/exist"),
+            ]),
+        );
+        assert!(config.is_ok());
+        let config = config.unwrap();
+        assert_eq!(
+            Some("{{ advisories | length }}"),
+            config.title_template.as_deref()
+        );
+        assert!(config.body_template.is_none());
+    }
+
+    #[test]
+    fn bad_template_fails() {
+        let config = Config::from_lookup(
+            Cli::default(),
+            lookup(&[("INPUT_ISSUE_BODY_TEMPLATE", "{% for a in advisories %}")]),
+        );
+        assert_eq!(Some("issue_body_template"), invalid_input(config));
+    }
+
+    #[test]
+    fn missing_template_file_fails() {
+        let config = Config::from_lookup(
+            Cli::default(),
+            lookup(&[("INPUT_ISSUE_BODY_TEMPLATE_FILE", "does/not/exist")]),
+        );
+        assert_eq!(
+            Some("INPUT_ISSUE_BODY_TEMPLATE_FILE"),
+            invalid_input(config)
+        );
+    }
+}
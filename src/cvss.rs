@@ -0,0 +1,166 @@
+// Copyright (c) 2023 audit-check developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or https://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use serde::Serialize;
+use std::{collections::BTreeMap, fmt};
+
+/// The qualitative severity rating of a CVSS base score.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Severity {
+    None,
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl Severity {
+    pub(crate) fn from_score(score: f64) -> Self {
+        match score {
+            s if s >= 9.0 => Self::Critical,
+            s if s >= 7.0 => Self::High,
+            s if s >= 4.0 => Self::Medium,
+            s if s > 0.0 => Self::Low,
+            _ => Self::None,
+        }
+    }
+
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+            Self::Critical => "critical",
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The base score of a CVSS v3 vector, i.e.
+/// `CVSS:3.1/AV:N/AC:H/PR:N/UI:N/S:U/C:H/I:H/A:H`.
+///
+/// Other CVSS versions and incomplete vectors have no score.
+pub(crate) fn score(vector: &str) -> Option<f64> {
+    let mut parts = vector.split('/');
+    if !matches!(parts.next()?, "CVSS:3.0" | "CVSS:3.1") {
+        return None;
+    }
+    let metrics: BTreeMap<&str, &str> = parts.filter_map(|part| part.split_once(':')).collect();
+    let metric = |name: &str| metrics.get(name).copied();
+
+    let changed = match metric("S")? {
+        "U" => false,
+        "C" => true,
+        _ => return None,
+    };
+    let attack_vector = match metric("AV")? {
+        "N" => 0.85,
+        "A" => 0.62,
+        "L" => 0.55,
+        "P" => 0.2,
+        _ => return None,
+    };
+    let attack_complexity = match metric("AC")? {
+        "L" => 0.77,
+        "H" => 0.44,
+        _ => return None,
+    };
+    let privileges_required = match (metric("PR")?, changed) {
+        ("N", _) => 0.85,
+        ("L", false) => 0.62,
+        ("L", true) => 0.68,
+        ("H", false) => 0.27,
+        ("H", true) => 0.5,
+        _ => return None,
+    };
+    let user_interaction = match metric("UI")? {
+        "N" => 0.85,
+        "R" => 0.62,
+        _ => return None,
+    };
+    let impact = |name: &str| match metric(name)? {
+        "H" => Some(0.56),
+        "L" => Some(0.22),
+        "N" => Some(0.0),
+        _ => None,
+    };
+    let iss: f64 = 1.0 - (1.0 - impact("C")?) * (1.0 - impact("I")?) * (1.0 - impact("A")?);
+
+    let impact = if changed {
+        7.52 * (iss - 0.029) - 3.25 * (iss - 0.02).powi(15)
+    } else {
+        6.42 * iss
+    };
+    let exploitability: f64 =
+        8.22 * attack_vector * attack_complexity * privileges_required * user_interaction;
+    Some(if impact <= 0.0 {
+        0.0
+    } else if changed {
+        round_up((1.08 * (impact + exploitability)).min(10.0))
+    } else {
+        round_up((impact + exploitability).min(10.0))
+    })
+}
+
+// The smallest number, to one decimal place, that is equal to or higher than
+// its input, avoiding floating point errors as in the CVSS 3.1 specification.
+fn round_up(value: f64) -> f64 {
+    let int = (value * 100_000.0).round();
+    if (int % 10_000.0).abs() < f64::EPSILON {
+        int / 100_000.0
+    } else {
+        ((int / 10_000.0).floor() + 1.0) / 10.0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Severity, score};
+
+    #[test]
+    fn score_works() {
+        assert_eq!(
+            Some(8.1),
+            score("CVSS:3.1/AV:N/AC:H/PR:N/UI:N/S:U/C:H/I:H/A:H")
+        );
+        assert_eq!(
+            Some(9.8),
+            score("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H")
+        );
+        assert_eq!(
+            Some(10.0),
+            score("CVSS:3.0/AV:N/AC:L/PR:N/UI:N/S:C/C:H/I:H/A:H")
+        );
+        assert_eq!(
+            Some(5.5),
+            score("CVSS:3.1/AV:L/AC:L/PR:N/UI:R/S:U/C:N/I:N/A:H")
+        );
+        assert_eq!(
+            Some(0.0),
+            score("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:N/I:N/A:N")
+        );
+        assert_eq!(None, score("CVSS:4.0/AV:N/AC:L/AT:N/PR:N/UI:N"));
+        assert_eq!(None, score("CVSS:3.1/AV:N/AC:L"));
+    }
+
+    #[test]
+    fn from_score_works() {
+        assert_eq!(Severity::Critical, Severity::from_score(9.8));
+        assert_eq!(Severity::High, Severity::from_score(8.1));
+        assert_eq!(Severity::Medium, Severity::from_score(4.0));
+        assert_eq!(Severity::Low, Severity::from_score(0.1));
+        assert_eq!(Severity::None, Severity::from_score(0.0));
+    }
+}
@@ -0,0 +1,152 @@
+// Copyright (c) 2023 audit-check developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or https://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use std::fmt;
+use tracing::{error, info, warn};
+
+/// What a line `cargo audit` wrote to stderr is about.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum DiagnosticKind {
+    /// The advisory database could not be fetched or loaded
+    DatabaseFetch,
+    /// `Cargo.lock` could not be found or parsed
+    Lockfile,
+    /// `cargo audit` panicked
+    Crash,
+    /// Any other error
+    Error,
+    /// A warning
+    Warning,
+    /// Progress, i.e. fetching the database or scanning the lockfile
+    Progress,
+}
+
+impl DiagnosticKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::DatabaseFetch => "advisory database error",
+            Self::Lockfile => "lockfile error",
+            Self::Crash => "crash",
+            Self::Error => "error",
+            Self::Warning => "warning",
+            Self::Progress => "progress",
+        }
+    }
+}
+
+impl fmt::Display for DiagnosticKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A classified line of `cargo audit` stderr output.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct Diagnostic {
+    pub(crate) kind: DiagnosticKind,
+    pub(crate) message: String,
+}
+
+impl Diagnostic {
+    /// Classify a line of stderr output.
+    ///
+    /// `cargo audit` prefixes errors and warnings the way cargo does, the rest
+    /// of its stderr output is progress.
+    pub(crate) fn classify(line: &str) -> Self {
+        let message = line.trim();
+        let lower = message.to_lowercase();
+        let kind = if lower.contains("panicked at") {
+            DiagnosticKind::Crash
+        } else if let Some(error) = lower.strip_prefix("error:") {
+            // the findings themselves, i.e. "error: 2 vulnerabilities found!"
+            if error.contains("vulnerabilit") && error.contains("found") {
+                DiagnosticKind::Warning
+            } else if error.contains("advisory database") || error.contains("advisory-db") {
+                DiagnosticKind::DatabaseFetch
+            } else if error.contains("cargo.lock") || error.contains("lockfile") {
+                DiagnosticKind::Lockfile
+            } else {
+                DiagnosticKind::Error
+            }
+        } else if lower.starts_with("warning:") {
+            DiagnosticKind::Warning
+        } else {
+            DiagnosticKind::Progress
+        };
+        Self {
+            kind,
+            message: message.to_string(),
+        }
+    }
+
+    /// Whether this diagnostic means `cargo audit` failed.
+    pub(crate) fn is_error(&self) -> bool {
+        !matches!(
+            self.kind,
+            DiagnosticKind::Warning | DiagnosticKind::Progress
+        )
+    }
+
+    /// Log the diagnostic at the level matching its kind.
+    pub(crate) fn log(&self) {
+        match self.kind {
+            DiagnosticKind::Progress => info!("{}", self.message),
+            DiagnosticKind::Warning => warn!("{}", self.message),
+            _ => error!("cargo audit {}: {}", self.kind, self.message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Diagnostic, DiagnosticKind};
+
+    #[test]
+    fn classify_works() {
+        let kind = |line: &str| Diagnostic::classify(line).kind;
+        assert_eq!(
+            DiagnosticKind::Progress,
+            kind(
+                "    Fetching advisory database from `https://github.com/RustSec/advisory-db.git`"
+            )
+        );
+        assert_eq!(
+            DiagnosticKind::Progress,
+            kind("      Loaded 734 security advisories (from /github/home/.cargo/advisory-db)")
+        );
+        assert_eq!(
+            DiagnosticKind::DatabaseFetch,
+            kind(
+                "error: couldn't fetch advisory database: git operation failed: failed to prepare fetch"
+            )
+        );
+        assert_eq!(
+            DiagnosticKind::Lockfile,
+            kind(
+                "error: not found: Couldn't load Cargo.lock: I/O operation failed: No such file or directory"
+            )
+        );
+        assert_eq!(
+            DiagnosticKind::Crash,
+            kind("thread 'main' panicked at src/bin/cargo-audit/main.rs:10:5:")
+        );
+        assert_eq!(DiagnosticKind::Error, kind("error: invalid value for '-D'"));
+        assert_eq!(
+            DiagnosticKind::Warning,
+            kind("warning: 1 allowed warning found")
+        );
+    }
+
+    #[test]
+    fn is_error_works() {
+        assert!(Diagnostic::classify("error: couldn't fetch advisory database").is_error());
+        assert!(!Diagnostic::classify("warning: 1 allowed warning found").is_error());
+        assert!(!Diagnostic::classify("error: 2 vulnerabilities found!").is_error());
+        assert!(!Diagnostic::classify("    Scanning Cargo.lock for vulnerabilities").is_error());
+    }
+}
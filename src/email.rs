@@ -0,0 +1,231 @@
+// Copyright (c) 2023 audit-check developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or https://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use crate::{
+    advisory::Rustsec,
+    config::Email,
+    template::{advisory_context, bundled},
+    toolchain::Toolchain,
+};
+use anyhow::Result;
+use clap::ValueEnum;
+use lettre::{
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor, message::MultiPart,
+    transport::smtp::authentication::Credentials,
+};
+use minijinja::context;
+use serde::Deserialize;
+
+const TEXT_TEMPLATE: &str = include_str!("email/digest.txt");
+const HTML_TEMPLATE: &str = include_str!("email/digest.html");
+
+/// How the connection to the SMTP server is secured.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum SmtpTls {
+    /// Plain text, only for local relays
+    None,
+    /// Upgrade the connection with STARTTLS
+    #[default]
+    Starttls,
+    /// Implicit TLS
+    Tls,
+}
+
+impl SmtpTls {
+    /// The port the SMTP server listens on by default.
+    pub(crate) fn default_port(self) -> u16 {
+        match self {
+            Self::None => 25,
+            Self::Starttls => 587,
+            Self::Tls => 465,
+        }
+    }
+}
+
+// The rendered email, before it is encoded.
+#[derive(Clone, Debug)]
+struct Digest {
+    subject: String,
+    text: String,
+    html: String,
+}
+
+/// Build the email with a plain text and an HTML digest of the advisories.
+pub(crate) fn message(
+    email: &Email,
+    repository: Option<&str>,
+    rustsecs: &[Rustsec],
+    toolchain: &Toolchain,
+) -> Result<Message> {
+    let Digest {
+        subject,
+        text,
+        html,
+    } = digest(repository, rustsecs, toolchain)?;
+    let mut builder = Message::builder().from(email.from.clone()).subject(subject);
+    for to in &email.to {
+        builder = builder.to(to.clone());
+    }
+    Ok(builder.multipart(MultiPart::alternative_plain_html(text, html))?)
+}
+
+/// Send the email through the configured SMTP server.
+pub(crate) async fn send(email: &Email, message: Message) -> Result<()> {
+    let mut transport = match email.tls {
+        SmtpTls::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&email.host),
+        SmtpTls::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&email.host)?,
+        SmtpTls::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&email.host)?,
+    }
+    .port(email.port);
+    if let (Some(username), Some(password)) = (&email.username, &email.password) {
+        transport = transport.credentials(Credentials::new(username.clone(), password.clone()));
+    }
+    let _response = transport.build().send(message).await?;
+    Ok(())
+}
+
+fn digest(repository: Option<&str>, rustsecs: &[Rustsec], toolchain: &Toolchain) -> Result<Digest> {
+    let subject = subject(repository, rustsecs);
+    let advisories = advisory_context(rustsecs);
+    let context = context! { subject, repository, advisories, toolchain };
+
+    let env = bundled(&[
+        ("digest.txt", TEXT_TEMPLATE),
+        ("digest.html", HTML_TEMPLATE),
+    ])?;
+    Ok(Digest {
+        text: env.get_template("digest.txt")?.render(&context)?,
+        html: env.get_template("digest.html")?.render(&context)?,
+        subject,
+    })
+}
+
+fn subject(repository: Option<&str>, rustsecs: &[Rustsec]) -> String {
+    let mut subject = match rustsecs.len() {
+        1 => "cargo audit found 1 advisory".to_string(),
+        count => format!("cargo audit found {count} advisories"),
+    };
+    if let Some(repository) = repository {
+        subject.push_str(" in ");
+        subject.push_str(repository);
+    }
+    subject
+}
+
+#[cfg(test)]
+mod test {
+    use super::{SmtpTls, digest, message, send};
+    use crate::{
+        advisory::{parse, test::TEST_REPORT},
+        config::Email,
+        toolchain::test::toolchain,
+    };
+    use anyhow::Result;
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+        net::TcpListener,
+    };
+
+    fn email(port: u16) -> Result<Email> {
+        Ok(Email {
+            host: "127.0.0.1".to_string(),
+            port,
+            tls: SmtpTls::None,
+            username: Some("audit".to_string()),
+            password: Some("secret".to_string()),
+            from: "audit-check <audit@example.com>".parse()?,
+            to: vec!["security@example.com".parse()?, "ops@example.com".parse()?],
+        })
+    }
+
+    // A minimal SMTP server accepting a single message, returning everything
+    // the client sent.
+    async fn sink(listener: TcpListener) -> Result<String> {
+        let (stream, _) = listener.accept().await?;
+        let (read, mut write) = stream.into_split();
+        let mut lines = BufReader::new(read).lines();
+        let mut received = String::new();
+        let mut data = false;
+        write.write_all(b"220 localhost ESMTP sink\r\n").await?;
+        while let Some(line) = lines.next_line().await? {
+            received.push_str(&line);
+            received.push('\n');
+            if data {
+                if line == "." {
+                    data = false;
+                    write.write_all(b"250 OK\r\n").await?;
+                }
+                continue;
+            }
+            let command = line.get(..4).unwrap_or(&line).to_uppercase();
+            let reply: &[u8] = match command.as_str() {
+                "EHLO" => b"250-localhost\r\n250 AUTH PLAIN\r\n",
+                "AUTH" => b"235 Authentication succeeded\r\n",
+                "DATA" => {
+                    data = true;
+                    b"354 End data with <CR><LF>.<CR><LF>\r\n"
+                }
+                "QUIT" => b"221 Bye\r\n",
+                _ => b"250 OK\r\n",
+            };
+            write.write_all(reply).await?;
+            if command == "QUIT" {
+                break;
+            }
+        }
+        Ok(received)
+    }
+
+    #[test]
+    fn digest_works() -> Result<()> {
+        let digest = digest(Some("a/b"), &parse(TEST_REPORT)?, &toolchain())?;
+        assert_eq!("cargo audit found 4 advisories in a/b", digest.subject);
+        assert!(
+            digest
+                .text
+                .starts_with("cargo audit found 4 advisories in a/b.\n\n")
+        );
+        assert!(digest.text.contains(
+            "RUSTSEC-2020-0099: Aovec<T> lacks bound on its Send and Sync traits allowing data races\n  Crate:     aovec 1.1.0\n"
+        ));
+        assert!(digest.text.contains("cargo audit 0.21.2"));
+        // titles are escaped in the HTML part
+        assert!(digest.html.contains("Aovec&lt;T&gt; lacks bound"));
+        Ok(())
+    }
+
+    #[test]
+    fn message_works() -> Result<()> {
+        let message = message(&email(25)?, None, &parse(TEST_REPORT)?, &toolchain())?;
+        let formatted = String::from_utf8(message.formatted())?;
+        assert!(formatted.contains("Subject: cargo audit found 4 advisories\r\n"));
+        assert!(formatted.contains("To: security@example.com, ops@example.com"));
+        assert!(formatted.contains("multipart/alternative"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn send_works() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let port = listener.local_addr()?.port();
+        let sink = tokio::spawn(sink(listener));
+        let email = email(port)?;
+
+        let message = message(&email, None, &parse(TEST_REPORT)?, &toolchain())?;
+        send(&email, message).await?;
+
+        let received = sink.await??;
+        assert!(received.contains("AUTH PLAIN"));
+        assert!(received.contains("MAIL FROM:<audit@example.com>"));
+        assert!(received.contains("RCPT TO:<security@example.com>"));
+        assert!(received.contains("RCPT TO:<ops@example.com>"));
+        assert!(received.contains("Subject: cargo audit found 4 advisories"));
+        Ok(())
+    }
+}
@@ -6,9 +6,72 @@
 // option. All files in the project carrying such notice may not be copied,
 // modified, or distributed except according to those terms.
 
+use std::time::Duration;
 use thiserror::Error;
 use tracing_subscriber::util::TryInitError;
 
+/// Advisories were found.
+pub(crate) const EXIT_ADVISORIES: u8 = 1;
+/// The configuration is invalid or incomplete, also used by the argument parser.
+pub(crate) const EXIT_CONFIG: u8 = 2;
+/// The rustc or cargo audit requirements are not met.
+pub(crate) const EXIT_TOOLCHAIN: u8 = 3;
+/// cargo audit failed to run or its output could not be read.
+pub(crate) const EXIT_AUDIT: u8 = 4;
+/// A GitHub API or webhook request failed, i.e. the issue could not be created.
+pub(crate) const EXIT_NOTIFIER: u8 = 5;
+/// Any other failure.
+pub(crate) const EXIT_UNEXPECTED: u8 = 6;
+/// Only informational warnings, i.e. unmaintained crates, failed the check.
+pub(crate) const EXIT_WARNINGS: u8 = 7;
+
+/// An error returned by the library API.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// The output is not a `cargo audit` report
+    #[error("unable to parse the cargo audit output: {reason}")]
+    AuditOutput {
+        /// Why the output could not be parsed
+        reason: String,
+    },
+    /// The contents are not a `Cargo.lock`
+    #[error("unable to parse the lockfile: {reason}")]
+    Lockfile {
+        /// Why the lockfile could not be parsed
+        reason: String,
+    },
+    /// The check did not pass, see [`run_cli`](crate::run_cli)
+    #[error("{message}")]
+    Check {
+        /// What failed, with its causes
+        message: String,
+        /// The exit code, see the README for the full list
+        code: u8,
+    },
+}
+
+impl Error {
+    /// The process exit code for this error.
+    #[must_use]
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Self::AuditOutput { .. } => EXIT_AUDIT,
+            Self::Lockfile { .. } => EXIT_UNEXPECTED,
+            Self::Check { code, .. } => *code,
+        }
+    }
+}
+
+impl From<anyhow::Error> for Error {
+    fn from(e: anyhow::Error) -> Self {
+        Self::Check {
+            message: format!("{e:#}"),
+            code: exit_code(&e),
+        }
+    }
+}
+
 #[derive(Debug, Error)]
 pub(crate) enum AuditCheckError {
     #[error("An error has occurred")]
@@ -19,16 +82,163 @@ pub(crate) enum AuditCheckError {
     Stdout,
     #[error("Unable to determine status code")]
     Code,
-    #[error("Error joining thread handle")]
-    Join,
-    #[error("cargo audit version check failed")]
-    AuditVersionCheck,
+    #[error("unable to parse the cargo audit output: {reason}")]
+    AuditOutput { reason: String },
+    #[error("cargo audit exited with code {code} without reporting a denied advisory")]
+    AuditExit { code: i32 },
+    #[error("cargo audit failed: {message}")]
+    AuditFailed { message: String },
+    #[error("cargo audit did not finish within {}s and was killed", timeout.as_secs_f64())]
+    Timeout { timeout: Duration },
+    #[error("cargo audit was cancelled")]
+    Cancelled,
+    #[error("cargo audit is not installed, install it with 'cargo install cargo-audit --locked'")]
+    AuditMissing,
+    #[error("unable to install cargo audit: {reason}")]
+    AuditInstall { reason: String },
+    #[error("unable to determine the cargo audit version from '{output}'")]
+    AuditVersion { output: String },
+    #[error("cargo audit {found} is installed, but {required} or greater is required")]
+    AuditVersionCheck {
+        found: String,
+        required: &'static str,
+    },
     #[error("cargo audit requires rust {msrv:?} or greater")]
     RustcVersionCheck { msrv: &'static str },
+    #[error("required input '{input}' is not set")]
+    MissingInput { input: &'static str },
+    #[error("invalid value for input '{input}': {reason}")]
+    InvalidInput { input: &'static str, reason: String },
+    #[error("invalid config file '{path}': {message}")]
+    ConfigFile { path: String, message: String },
     #[error("failed to initialize tracing")]
     TryInit(#[from] TryInitError),
     #[error("Unable to create an issue")]
     CreateIssue,
+    #[error("GitHub API request failed with status {status}: {message}")]
+    GitHubApi { status: u16, message: String },
+    #[error("webhook request failed with status {status}: {message}")]
+    Webhook { status: u16, message: String },
     #[error("RUSTSEC issue found")]
     RustSec,
+    #[error("RUSTSEC informational warning found")]
+    Warnings,
+}
+
+impl AuditCheckError {
+    /// The process exit code for this error, see the README for the full list.
+    pub(crate) fn exit_code(&self) -> u8 {
+        match self {
+            Self::Anyhow(e) => exit_code(e),
+            Self::RustSec => EXIT_ADVISORIES,
+            Self::Warnings => EXIT_WARNINGS,
+            Self::MissingInput { .. } | Self::InvalidInput { .. } | Self::ConfigFile { .. } => {
+                EXIT_CONFIG
+            }
+            Self::AuditMissing
+            | Self::AuditInstall { .. }
+            | Self::AuditVersion { .. }
+            | Self::AuditVersionCheck { .. }
+            | Self::RustcVersionCheck { .. } => EXIT_TOOLCHAIN,
+            Self::Stderr
+            | Self::Stdout
+            | Self::Code
+            | Self::AuditOutput { .. }
+            | Self::AuditExit { .. }
+            | Self::AuditFailed { .. }
+            | Self::Timeout { .. }
+            | Self::Cancelled => EXIT_AUDIT,
+            Self::CreateIssue | Self::GitHubApi { .. } | Self::Webhook { .. } => EXIT_NOTIFIER,
+            Self::TryInit(_) => EXIT_UNEXPECTED,
+        }
+    }
+}
+
+/// The process exit code for the error, based on the first [`AuditCheckError`] in its chain.
+pub(crate) fn exit_code(e: &anyhow::Error) -> u8 {
+    e.chain()
+        .find_map(|cause| cause.downcast_ref::<AuditCheckError>())
+        .map_or(EXIT_UNEXPECTED, AuditCheckError::exit_code)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        AuditCheckError, EXIT_ADVISORIES, EXIT_AUDIT, EXIT_CONFIG, EXIT_NOTIFIER, EXIT_TOOLCHAIN,
+        EXIT_UNEXPECTED, EXIT_WARNINGS, Error, exit_code,
+    };
+    use anyhow::anyhow;
+    use std::time::Duration;
+
+    #[test]
+    fn exit_code_works() {
+        assert_eq!(EXIT_ADVISORIES, exit_code(&AuditCheckError::RustSec.into()));
+        assert_eq!(EXIT_WARNINGS, exit_code(&AuditCheckError::Warnings.into()));
+        assert_eq!(
+            EXIT_CONFIG,
+            exit_code(&AuditCheckError::MissingInput { input: "token" }.into())
+        );
+        assert_eq!(
+            EXIT_TOOLCHAIN,
+            exit_code(
+                &AuditCheckError::AuditVersionCheck {
+                    found: "0.17.6".to_string(),
+                    required: "0.18.0"
+                }
+                .into()
+            )
+        );
+        assert_eq!(EXIT_AUDIT, exit_code(&AuditCheckError::Code.into()));
+        assert_eq!(
+            EXIT_AUDIT,
+            exit_code(
+                &AuditCheckError::Timeout {
+                    timeout: Duration::from_secs(1)
+                }
+                .into()
+            )
+        );
+        assert_eq!(
+            EXIT_NOTIFIER,
+            exit_code(
+                &AuditCheckError::GitHubApi {
+                    status: 500,
+                    message: "Server Error".to_string()
+                }
+                .into()
+            )
+        );
+        assert_eq!(
+            EXIT_NOTIFIER,
+            exit_code(
+                &AuditCheckError::Webhook {
+                    status: 503,
+                    message: "Service Unavailable".to_string()
+                }
+                .into()
+            )
+        );
+        assert_eq!(EXIT_UNEXPECTED, exit_code(&anyhow!("boom")));
+    }
+
+    #[test]
+    fn exit_code_looks_through_context() {
+        let e = anyhow::Error::from(AuditCheckError::AuditMissing)
+            .context("cargo audit check has failed");
+        assert_eq!(EXIT_TOOLCHAIN, exit_code(&e));
+        let e = anyhow::Error::from(AuditCheckError::Anyhow(AuditCheckError::Stdout.into()));
+        assert_eq!(EXIT_AUDIT, exit_code(&e));
+    }
+
+    #[test]
+    fn error_keeps_the_exit_code() {
+        let e = Error::from(
+            anyhow::Error::from(AuditCheckError::AuditMissing)
+                .context("cargo audit check has failed"),
+        );
+        assert_eq!(EXIT_TOOLCHAIN, e.exit_code());
+        assert!(e.to_string().starts_with("cargo audit check has failed"));
+        assert!(e.to_string().contains("cargo audit is not installed"));
+        assert!(!e.to_string().contains('\n'));
+    }
 }
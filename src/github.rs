@@ -0,0 +1,362 @@
+// Copyright (c) 2023 audit-check developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or https://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use crate::error::AuditCheckError;
+use anyhow::Result;
+use reqwest::{
+    Client, RequestBuilder, Response, StatusCode,
+    header::{HeaderMap, HeaderValue, RETRY_AFTER},
+};
+use serde::Deserialize;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::time::sleep;
+use tracing::warn;
+
+/// The maximum number of attempts made for a single request.
+const MAX_ATTEMPTS: u32 = 5;
+/// The initial backoff delay, doubled on every retry.
+const BASE_DELAY: Duration = Duration::from_secs(1);
+/// The upper bound for the exponential backoff delay.
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+/// Rate limit resets further away than this are reported rather than waited out.
+const MAX_RATE_LIMIT_WAIT: Duration = Duration::from_mins(5);
+
+const RATE_LIMIT_REMAINING: &str = "x-ratelimit-remaining";
+const RATE_LIMIT_RESET: &str = "x-ratelimit-reset";
+
+static APP_USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"),);
+
+#[derive(Clone, Debug, Deserialize)]
+struct ApiError {
+    message: String,
+}
+
+/// The HTTP client shared by the GitHub API and webhook requests.
+pub(crate) fn client() -> Result<Client> {
+    Ok(Client::builder().user_agent(APP_USER_AGENT).build()?)
+}
+
+/// The headers sent with every GitHub API request.
+pub(crate) fn api_headers() -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    let _old = headers.insert(
+        "Accept",
+        HeaderValue::from_static("application/vnd.github+json"),
+    );
+    let _old = headers.insert(
+        "X-GitHub-Api-Version",
+        HeaderValue::from_static("2022-11-28"),
+    );
+    headers
+}
+
+/// Send the request, retrying on server errors, network errors and rate limiting.
+///
+/// Server errors and network errors are retried with exponential backoff,
+/// or after the `Retry-After` delay when one is given. Rate limited responses
+/// (429, or 403 with the rate limit exhausted) wait for the time given by
+/// `Retry-After` or `X-RateLimit-Reset`.  Any other non-success response is
+/// turned into [`AuditCheckError::GitHubApi`].
+///
+/// Requests that are not idempotent, i.e. creating an issue, may already have
+/// been processed when a 500 or a timeout comes back, so they are only
+/// retried when they never reached GitHub, were rate limited or were turned
+/// away by the gateway (502, 503 or 504).
+pub(crate) async fn send_with_retry(request: RequestBuilder) -> Result<Response> {
+    let res = retry(request, "GitHub API").await?;
+    if res.status().is_success() {
+        Ok(res)
+    } else {
+        Err(api_error(res).await)
+    }
+}
+
+/// Send the request with the retries of [`send_with_retry`], returning the
+/// last response, successful or not. `service` names what the request is
+/// sent to in the log.
+pub(crate) async fn retry(request: RequestBuilder, service: &str) -> Result<Response> {
+    let idempotent = request
+        .try_clone()
+        .and_then(|req| req.build().ok())
+        .is_some_and(|req| req.method().is_idempotent());
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let req = request
+            .try_clone()
+            .ok_or_else(|| anyhow::anyhow!("the {service} request cannot be retried"))?;
+
+        match req.send().await {
+            Ok(res) if res.status().is_success() => return Ok(res),
+            Ok(res) => {
+                let status = res.status();
+                match retry_delay(
+                    status,
+                    res.headers(),
+                    attempt,
+                    idempotent,
+                    SystemTime::now(),
+                ) {
+                    Some(delay) if attempt < MAX_ATTEMPTS => {
+                        warn!(
+                            "{service} responded with {status}, retrying in {}s (attempt {attempt} of {MAX_ATTEMPTS})",
+                            delay.as_secs()
+                        );
+                        sleep(delay).await;
+                    }
+                    _ => return Ok(res),
+                }
+            }
+            Err(e) if attempt < MAX_ATTEMPTS && is_transient(&e, idempotent) => {
+                let delay = backoff(attempt);
+                warn!(
+                    "{service} request failed: {e}, retrying in {}s (attempt {attempt} of {MAX_ATTEMPTS})",
+                    delay.as_secs()
+                );
+                sleep(delay).await;
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+// A request that failed to connect was never sent, anything else may have
+// been.
+fn is_transient(e: &reqwest::Error, idempotent: bool) -> bool {
+    e.is_connect() || (idempotent && (e.is_timeout() || e.is_request()))
+}
+
+fn backoff(attempt: u32) -> Duration {
+    BASE_DELAY
+        .saturating_mul(2_u32.saturating_pow(attempt.saturating_sub(1)))
+        .min(MAX_BACKOFF)
+}
+
+fn retry_delay(
+    status: StatusCode,
+    headers: &HeaderMap,
+    attempt: u32,
+    idempotent: bool,
+    now: SystemTime,
+) -> Option<Duration> {
+    let retry_after = header_u64(headers, RETRY_AFTER.as_str()).map(Duration::from_secs);
+    let delay = if is_rate_limited(status, headers) {
+        retry_after
+            .or_else(|| {
+                let reset = header_u64(headers, RATE_LIMIT_RESET)?;
+                let now = now.duration_since(UNIX_EPOCH).ok()?.as_secs();
+                Some(Duration::from_secs(reset.saturating_sub(now).max(1)))
+            })
+            .unwrap_or_else(|| backoff(attempt))
+    } else if is_gateway_error(status) || (idempotent && status.is_server_error()) {
+        retry_after.unwrap_or_else(|| backoff(attempt))
+    } else {
+        return None;
+    };
+    (delay <= MAX_RATE_LIMIT_WAIT).then_some(delay)
+}
+
+// Returned while GitHub is unavailable or overloaded, so they are retried
+// even for requests that are not idempotent.
+fn is_gateway_error(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+fn is_rate_limited(status: StatusCode, headers: &HeaderMap) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS
+        || (status == StatusCode::FORBIDDEN
+            && (headers.contains_key(RETRY_AFTER)
+                || header_u64(headers, RATE_LIMIT_REMAINING) == Some(0)))
+}
+
+fn header_u64(headers: &HeaderMap, name: &str) -> Option<u64> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+}
+
+async fn api_error(res: Response) -> anyhow::Error {
+    let status = res.status().as_u16();
+    let message = match res.bytes().await {
+        Ok(body) => serde_json::from_slice::<ApiError>(&body).map_or_else(
+            |_| String::from_utf8_lossy(&body).into_owned(),
+            |api_error| api_error.message,
+        ),
+        Err(e) => e.to_string(),
+    };
+    AuditCheckError::GitHubApi { status, message }.into()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{MAX_BACKOFF, backoff, retry_delay, send_with_retry};
+    use anyhow::Result;
+    use reqwest::{
+        Client, StatusCode,
+        header::{HeaderMap, HeaderValue},
+    };
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{method, path},
+    };
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            let _old = headers.insert(*name, HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    #[test]
+    fn backoff_doubles_and_caps() {
+        assert_eq!(Duration::from_secs(1), backoff(1));
+        assert_eq!(Duration::from_secs(2), backoff(2));
+        assert_eq!(Duration::from_secs(4), backoff(3));
+        assert_eq!(MAX_BACKOFF, backoff(32));
+    }
+
+    #[test]
+    fn server_errors_are_retried() {
+        let delay = retry_delay(
+            StatusCode::BAD_GATEWAY,
+            &HeaderMap::new(),
+            2,
+            true,
+            SystemTime::now(),
+        );
+        assert_eq!(Some(Duration::from_secs(2)), delay);
+        let delay = retry_delay(
+            StatusCode::SERVICE_UNAVAILABLE,
+            &headers(&[("retry-after", "3")]),
+            2,
+            false,
+            SystemTime::now(),
+        );
+        assert_eq!(Some(Duration::from_secs(3)), delay);
+        // the request may have been processed
+        let delay = retry_delay(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &HeaderMap::new(),
+            2,
+            false,
+            SystemTime::now(),
+        );
+        assert!(delay.is_none());
+    }
+
+    #[test]
+    fn client_errors_are_not_retried() {
+        let delay = retry_delay(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            &HeaderMap::new(),
+            1,
+            true,
+            SystemTime::now(),
+        );
+        assert!(delay.is_none());
+        let delay = retry_delay(
+            StatusCode::FORBIDDEN,
+            &headers(&[("x-ratelimit-remaining", "10")]),
+            1,
+            true,
+            SystemTime::now(),
+        );
+        assert!(delay.is_none());
+    }
+
+    #[test]
+    fn retry_after_is_honored() {
+        let delay = retry_delay(
+            StatusCode::FORBIDDEN,
+            &headers(&[("retry-after", "17")]),
+            1,
+            true,
+            SystemTime::now(),
+        );
+        assert_eq!(Some(Duration::from_secs(17)), delay);
+    }
+
+    #[test]
+    fn rate_limit_reset_is_honored() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let delay = retry_delay(
+            StatusCode::FORBIDDEN,
+            &headers(&[
+                ("x-ratelimit-remaining", "0"),
+                ("x-ratelimit-reset", "1700000042"),
+            ]),
+            1,
+            false,
+            now,
+        );
+        assert_eq!(Some(Duration::from_secs(42)), delay);
+    }
+
+    #[test]
+    fn long_rate_limit_waits_give_up() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let delay = retry_delay(
+            StatusCode::TOO_MANY_REQUESTS,
+            &headers(&[("x-ratelimit-reset", "1700003600")]),
+            1,
+            false,
+            now,
+        );
+        assert!(delay.is_none());
+    }
+
+    #[tokio::test]
+    async fn rate_limited_posts_are_retried() -> Result<()> {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/issues"))
+            .respond_with(ResponseTemplate::new(429).insert_header("retry-after", "0"))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/issues"))
+            .respond_with(ResponseTemplate::new(201))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let request = Client::new().post(format!("{}/issues", server.uri()));
+        assert_eq!(201, send_with_retry(request).await?.status().as_u16());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn unavailable_posts_are_retried() -> Result<()> {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/issues"))
+            .respond_with(ResponseTemplate::new(503).insert_header("retry-after", "0"))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/issues"))
+            .respond_with(ResponseTemplate::new(201))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let request = Client::new().post(format!("{}/issues", server.uri()));
+        assert_eq!(201, send_with_retry(request).await?.status().as_u16());
+        Ok(())
+    }
+}
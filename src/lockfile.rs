@@ -0,0 +1,213 @@
+// Copyright (c) 2023 audit-check developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or https://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use anyhow::Result;
+use serde::Deserialize;
+use std::{fs, path::Path};
+
+/// The maximum number of dependency paths collected for a single package.
+const MAX_PATHS: usize = 16;
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub(crate) struct Lockfile {
+    #[serde(default, rename = "package")]
+    pub(crate) packages: Vec<LockedPackage>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub(crate) struct LockedPackage {
+    pub(crate) name: String,
+    pub(crate) version: String,
+    /// Where the package comes from, `None` for workspace and path packages
+    #[serde(default)]
+    pub(crate) source: Option<String>,
+    #[serde(default)]
+    dependencies: Vec<String>,
+}
+
+impl Lockfile {
+    pub(crate) fn load<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Ok(Self::parse(&fs::read_to_string(path)?)?)
+    }
+
+    pub(crate) fn parse(contents: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(contents)
+    }
+
+    /// Every path from the given package up through its dependents to a root
+    /// package, i.e. a workspace member nothing else depends on.
+    ///
+    /// Each path starts with the given package and is formatted as a list of
+    /// `name version` entries.
+    pub(crate) fn dependency_paths(&self, name: &str, version: &str) -> Vec<Vec<String>> {
+        let Some(start) = self
+            .packages
+            .iter()
+            .position(|p| p.name == name && p.version == version)
+        else {
+            return vec![];
+        };
+
+        let dependents = self.dependents();
+        let mut paths = vec![];
+        let mut path = vec![start];
+        self.walk(&dependents, &mut path, &mut paths);
+        paths
+    }
+
+    fn walk(&self, dependents: &[Vec<usize>], path: &mut Vec<usize>, paths: &mut Vec<Vec<String>>) {
+        if paths.len() >= MAX_PATHS {
+            return;
+        }
+        let current = path[path.len() - 1];
+        let next: Vec<usize> = dependents[current]
+            .iter()
+            .copied()
+            .filter(|idx| !path.contains(idx))
+            .collect();
+
+        if next.is_empty() {
+            paths.push(path.iter().map(|idx| self.packages[*idx].id()).collect());
+        } else {
+            for idx in next {
+                path.push(idx);
+                self.walk(dependents, path, paths);
+                let _ = path.pop();
+            }
+        }
+    }
+
+    // Invert the dependency lists, so each package knows what depends on it.
+    fn dependents(&self) -> Vec<Vec<usize>> {
+        let mut dependents = vec![vec![]; self.packages.len()];
+        for (idx, package) in self.packages.iter().enumerate() {
+            for dependency in &package.dependencies {
+                if let Some(dep_idx) = self.resolve(dependency) {
+                    dependents[dep_idx].push(idx);
+                }
+            }
+        }
+        dependents
+    }
+
+    // Dependencies are recorded as `name`, `name version` or
+    // `name version (source)`, the version only being present when the name
+    // is ambiguous.
+    fn resolve(&self, dependency: &str) -> Option<usize> {
+        let mut parts = dependency.split_whitespace();
+        let name = parts.next()?;
+        match parts.next() {
+            Some(version) => self
+                .packages
+                .iter()
+                .position(|p| p.name == name && p.version == version),
+            None => self.packages.iter().position(|p| p.name == name),
+        }
+    }
+}
+
+impl LockedPackage {
+    fn id(&self) -> String {
+        format!("{} {}", self.name, self.version)
+    }
+
+    /// The package URL, i.e. `pkg:cargo/smallvec@0.4.5`, only packages with
+    /// a source are published and have one.
+    pub(crate) fn purl(&self) -> Option<String> {
+        self.source
+            .as_ref()
+            .map(|_source| purl(&self.name, &self.version))
+    }
+}
+
+/// The package URL of the given crate version.
+pub(crate) fn purl(name: &str, version: &str) -> String {
+    format!("pkg:cargo/{name}@{version}")
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::Lockfile;
+    use anyhow::Result;
+
+    pub(crate) const TEST_LOCKFILE: &str = r#"version = 3
+
+[[package]]
+name = "anymap"
+version = "0.12.1"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "aovec"
+version = "1.1.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+dependencies = [
+ "parking_lot",
+ "smallvec",
+]
+
+[[package]]
+name = "audit-check-test"
+version = "0.1.0"
+dependencies = [
+ "anymap",
+ "aovec",
+ "smallvec",
+]
+
+[[package]]
+name = "owning_ref"
+version = "0.3.3"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "parking_lot"
+version = "0.4.8"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+dependencies = [
+ "owning_ref",
+]
+
+[[package]]
+name = "smallvec"
+version = "0.4.5"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+"#;
+
+    #[test]
+    fn parse_works() -> Result<()> {
+        assert_eq!(6, Lockfile::parse(TEST_LOCKFILE)?.packages.len());
+        Ok(())
+    }
+
+    #[test]
+    fn dependency_paths_works() -> Result<()> {
+        let lockfile = Lockfile::parse(TEST_LOCKFILE)?;
+        assert_eq!(
+            vec![vec![
+                "owning_ref 0.3.3",
+                "parking_lot 0.4.8",
+                "aovec 1.1.0",
+                "audit-check-test 0.1.0"
+            ]],
+            lockfile.dependency_paths("owning_ref", "0.3.3")
+        );
+        assert_eq!(
+            vec![
+                vec!["smallvec 0.4.5", "aovec 1.1.0", "audit-check-test 0.1.0"],
+                vec!["smallvec 0.4.5", "audit-check-test 0.1.0"],
+            ],
+            lockfile.dependency_paths("smallvec", "0.4.5")
+        );
+        assert!(lockfile.dependency_paths("smallvec", "1.0.0").is_empty());
+        Ok(())
+    }
+}
@@ -7,6 +7,7 @@
 // modified, or distributed except according to those terms.
 
 use anyhow::Result;
+use std::io::stderr;
 use time::format_description::well_known::Iso8601;
 use tracing::{Level, metadata::LevelFilter};
 use tracing_subscriber::{
@@ -22,6 +23,7 @@ pub(crate) fn initialize(level: Level) -> Result<()> {
         .with_level(true)
         .with_ansi(true)
         .with_target(false)
+        .with_writer(stderr)
         .with_timer(UtcTime::new(Iso8601::DEFAULT));
     let filter_layer = LevelFilter::from(level);
     Ok(registry().with(format).with(filter_layer).try_init()?)
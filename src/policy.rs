@@ -0,0 +1,292 @@
+// Copyright (c) 2023 audit-check developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or https://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use crate::{
+    advisory::{AdvisoryKind, Rustsec},
+    config::{Config, Ignore},
+    error::AuditCheckError,
+};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// What to do when an advisory of a given kind is found.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum Policy {
+    /// Fail the check
+    Fail,
+    /// Report the advisory without failing the check
+    Warn,
+    /// Drop the advisory
+    Ignore,
+}
+
+impl fmt::Display for Policy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Fail => "fail",
+            Self::Warn => "warn",
+            Self::Ignore => "ignore",
+        })
+    }
+}
+
+impl Policy {
+    /// What happened to an advisory this policy was applied to.
+    pub(crate) fn outcome(self) -> &'static str {
+        match self {
+            Self::Fail => "failed",
+            Self::Warn => "warned",
+            Self::Ignore => "ignored",
+        }
+    }
+}
+
+/// The policy for each kind of advisory.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Policies {
+    vulnerability: Policy,
+    unsound: Policy,
+    unmaintained: Policy,
+    yanked: Policy,
+    notice: Policy,
+}
+
+impl Policies {
+    /// The policies matching `cargo audit -D <deny>`, vulnerabilities always
+    /// fail and the denied warnings fail, the remaining warnings only warn.
+    #[must_use]
+    pub fn from_deny(deny: &str) -> Self {
+        let policy = |kind: AdvisoryKind| {
+            if deny == "warnings" || deny == kind.as_str() {
+                Policy::Fail
+            } else {
+                Policy::Warn
+            }
+        };
+        Self {
+            vulnerability: Policy::Fail,
+            unsound: policy(AdvisoryKind::Unsound),
+            unmaintained: policy(AdvisoryKind::Unmaintained),
+            yanked: policy(AdvisoryKind::Yanked),
+            notice: policy(AdvisoryKind::Notice),
+        }
+    }
+
+    /// The policy for the given kind of advisory.
+    #[must_use]
+    pub fn get(self, kind: AdvisoryKind) -> Policy {
+        match kind {
+            AdvisoryKind::Vulnerability => self.vulnerability,
+            AdvisoryKind::Unsound => self.unsound,
+            AdvisoryKind::Unmaintained => self.unmaintained,
+            AdvisoryKind::Yanked => self.yanked,
+            AdvisoryKind::Notice => self.notice,
+        }
+    }
+
+    /// Change the policy for the given kind of advisory.
+    pub fn set(&mut self, kind: AdvisoryKind, policy: Policy) {
+        let current = match kind {
+            AdvisoryKind::Vulnerability => &mut self.vulnerability,
+            AdvisoryKind::Unsound => &mut self.unsound,
+            AdvisoryKind::Unmaintained => &mut self.unmaintained,
+            AdvisoryKind::Yanked => &mut self.yanked,
+            AdvisoryKind::Notice => &mut self.notice,
+        };
+        *current = policy;
+    }
+}
+
+/// The policy applied to an advisory, and why.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct Decision {
+    /// The policy applied
+    pub policy: Policy,
+    /// Why it applied, i.e. `unmaintained_policy is warn`
+    pub reason: String,
+}
+
+/// An advisory along with the policy decision made for it.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct Finding {
+    /// The advisory, see [`Rustsec`]
+    pub rustsec: Rustsec,
+    /// What to do with it
+    pub decision: Decision,
+}
+
+impl Finding {
+    /// Whether the advisory fails the check.
+    #[must_use]
+    pub fn is_failed(&self) -> bool {
+        self.decision.policy == Policy::Fail
+    }
+
+    /// Whether the advisory was dropped.
+    #[must_use]
+    pub fn is_ignored(&self) -> bool {
+        self.decision.policy == Policy::Ignore
+    }
+}
+
+/// Decide what to do with each advisory.
+///
+/// Explicitly ignored advisories are ignored regardless of their kind, the
+/// rest follow the policy for their kind.  Findings are sorted by priority,
+/// highest first.
+pub(crate) fn evaluate(config: &Config, rustsecs: Vec<Rustsec>) -> Vec<Finding> {
+    apply(config.policies, &config.ignore, rustsecs)
+}
+
+/// Decide what to do with each advisory given the policies and ignores, the
+/// first ignore of an advisory or one of its aliases wins, so the config file
+/// is consulted before `.cargo/audit.toml` and `deny.toml`.
+pub(crate) fn apply(policies: Policies, ignore: &[Ignore], rustsecs: Vec<Rustsec>) -> Vec<Finding> {
+    let mut findings: Vec<Finding> = rustsecs
+        .into_iter()
+        .map(|rustsec| {
+            let ignored = ignore
+                .iter()
+                .find(|ignore| ignore.id == rustsec.id || rustsec.aliases.contains(&ignore.id));
+            let decision = if let Some(ignore) = ignored {
+                Decision {
+                    policy: Policy::Ignore,
+                    reason: format!("ignored by '{}': {}", ignore.source, ignore.reason),
+                }
+            } else {
+                let policy = policies.get(rustsec.kind);
+                Decision {
+                    policy,
+                    reason: format!("{}_policy is {policy}", rustsec.kind),
+                }
+            };
+            Finding { rustsec, decision }
+        })
+        .collect();
+    findings.sort_by_key(|finding| std::cmp::Reverse(finding.rustsec.kind.priority()));
+    findings
+}
+
+/// The overall result, failed vulnerabilities take precedence over failed
+/// informational warnings.
+pub(crate) fn outcome(findings: &[Finding]) -> Result<()> {
+    let failed = findings.iter().filter(|finding| finding.is_failed());
+    match failed.map(|finding| finding.rustsec.kind).min() {
+        Some(AdvisoryKind::Vulnerability) => Err(AuditCheckError::RustSec.into()),
+        Some(_) => Err(AuditCheckError::Warnings.into()),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Policies, Policy, evaluate, outcome};
+    use crate::{
+        advisory::{AdvisoryKind, parse, test::TEST_REPORT},
+        config::{Ignore, test::config},
+        error::AuditCheckError,
+    };
+    use anyhow::Result;
+
+    #[test]
+    fn from_deny_works() {
+        let policies = Policies::from_deny("warnings");
+        assert!(
+            AdvisoryKind::ALL
+                .iter()
+                .all(|kind| policies.get(*kind) == Policy::Fail)
+        );
+        let policies = Policies::from_deny("unsound");
+        assert_eq!(Policy::Fail, policies.get(AdvisoryKind::Vulnerability));
+        assert_eq!(Policy::Fail, policies.get(AdvisoryKind::Unsound));
+        assert_eq!(Policy::Warn, policies.get(AdvisoryKind::Unmaintained));
+        assert_eq!(Policy::Warn, policies.get(AdvisoryKind::Yanked));
+    }
+
+    #[test]
+    fn evaluate_works() -> Result<()> {
+        let mut config = config();
+        config
+            .policies
+            .set(AdvisoryKind::Unmaintained, Policy::Warn);
+        config.ignore.push(Ignore {
+            id: "RUSTSEC-2020-0099".to_string(),
+            reason: "aovec is only used in tests".to_string(),
+            source: ".github/audit-check.toml".to_string(),
+        });
+        let findings = evaluate(&config, parse(TEST_REPORT)?);
+        let decision = |id: &str| {
+            findings
+                .iter()
+                .find(|finding| finding.rustsec.id == id)
+                .map(|finding| finding.decision.policy)
+        };
+        assert_eq!(Some(Policy::Ignore), decision("RUSTSEC-2020-0099"));
+        assert_eq!(Some(Policy::Fail), decision("RUSTSEC-2022-0040"));
+        assert_eq!(Some(Policy::Warn), decision("RUSTSEC-2021-0065"));
+        assert_eq!(Some(Policy::Fail), decision("RUSTSEC-2018-0018"));
+        // highest priority first
+        assert_eq!(AdvisoryKind::Vulnerability, findings[0].rustsec.kind);
+        assert_eq!(AdvisoryKind::Unsound, findings[2].rustsec.kind);
+        assert_eq!(
+            "ignored by '.github/audit-check.toml': aovec is only used in tests",
+            findings[0].decision.reason
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn evaluate_matches_aliases() -> Result<()> {
+        let mut config = config();
+        config.ignore.push(Ignore::new(
+            "GHSA-9qxh-258v-666c",
+            "owning_ref is not used at runtime",
+            "deny.toml",
+        ));
+        let findings = evaluate(&config, parse(TEST_REPORT)?);
+        let owning_ref = findings
+            .iter()
+            .find(|finding| finding.rustsec.id == "RUSTSEC-2022-0040");
+        assert_eq!(
+            Some(Policy::Ignore),
+            owning_ref.map(|finding| finding.decision.policy)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn outcome_works() -> Result<()> {
+        let mut config = config();
+        let rustsecs = parse(TEST_REPORT)?;
+        assert!(matches!(
+            outcome(&evaluate(&config, rustsecs.clone()))
+                .err()
+                .map(anyhow::Error::downcast::<AuditCheckError>),
+            Some(Ok(AuditCheckError::RustSec))
+        ));
+        config
+            .policies
+            .set(AdvisoryKind::Vulnerability, Policy::Warn);
+        assert!(matches!(
+            outcome(&evaluate(&config, rustsecs.clone()))
+                .err()
+                .map(anyhow::Error::downcast::<AuditCheckError>),
+            Some(Ok(AuditCheckError::Warnings))
+        ));
+        for kind in AdvisoryKind::ALL {
+            config.policies.set(kind, Policy::Ignore);
+        }
+        assert!(outcome(&evaluate(&config, rustsecs)).is_ok());
+        Ok(())
+    }
+}
@@ -0,0 +1,59 @@
+// Copyright (c) 2023 audit-check developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or https://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Render advisories the way the action reports them.
+//!
+//! ```
+//! use audit_check::{AuditReport, Policies, Toolchain, render};
+//!
+//! # fn main() -> Result<(), audit_check::Error> {
+//! let report = AuditReport::parse(
+//!     r#"{"vulnerabilities": {"list": [{
+//!         "advisory": {"id": "RUSTSEC-2020-0099", "title": "Aovec<T> lacks bound", "date": "2020-12-10"},
+//!         "versions": {"patched": []},
+//!         "package": {"name": "aovec", "version": "1.1.0"}
+//!     }]}}"#,
+//! )?;
+//! let toolchain = Toolchain::from_parts("1.85.0", "x86_64-unknown-linux-gnu", "0.21.2");
+//!
+//! assert_eq!("RUSTSEC-2020-0099", render::title(&report.advisories));
+//! assert!(render::body(&report.advisories, &toolchain).starts_with("# ‼️ RUSTSEC-2020-0099 ‼️"));
+//!
+//! let findings = report.evaluate(Policies::from_deny("warnings"), &[]);
+//! assert!(render::summary(&findings, &toolchain).starts_with("## cargo audit"));
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    advisory::Rustsec,
+    policy::Finding,
+    reporter::issue::{default_body, default_title},
+    summary,
+    toolchain::Toolchain,
+};
+
+/// The issue title, the distinct advisory ids.
+#[must_use]
+pub fn title(advisories: &[Rustsec]) -> String {
+    default_title(advisories)
+}
+
+/// The issue body in Markdown, each advisory as `cargo audit` prints it
+/// followed by the toolchain.
+#[must_use]
+pub fn body(advisories: &[Rustsec], toolchain: &Toolchain) -> String {
+    default_body(advisories, toolchain)
+}
+
+/// The job summary in Markdown, a table of the findings followed by the
+/// toolchain.
+#[must_use]
+pub fn summary(findings: &[Finding], toolchain: &Toolchain) -> String {
+    summary::render(findings, &[], toolchain)
+}
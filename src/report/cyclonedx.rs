@@ -0,0 +1,243 @@
+// Copyright (c) 2023 audit-check developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or https://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use super::Report;
+use crate::{
+    advisory::AdvisoryKind,
+    lockfile::purl,
+    policy::{Finding, Policy},
+};
+use anyhow::Result;
+use serde::Serialize;
+use std::fs;
+
+const SPEC_VERSION: &str = "1.5";
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CycloneDx<'a> {
+    bom_format: &'static str,
+    spec_version: &'static str,
+    version: u32,
+    metadata: Metadata,
+    components: Vec<Component<'a>>,
+    vulnerabilities: Vec<Vulnerability<'a>>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct Metadata {
+    tools: Tools,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct Tools {
+    components: Vec<Tool>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct Tool {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    name: &'static str,
+    version: &'static str,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct Component<'a> {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    #[serde(rename = "bom-ref")]
+    bom_ref: String,
+    name: &'a str,
+    version: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    purl: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct Vulnerability<'a> {
+    id: &'a str,
+    source: Source<'a>,
+    description: &'a str,
+    recommendation: &'a str,
+    advisories: Vec<Advisory<'a>>,
+    affects: Vec<Affects>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    analysis: Option<Analysis<'a>>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct Source<'a> {
+    name: &'static str,
+    url: &'a str,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct Advisory<'a> {
+    title: &'a str,
+    url: &'a str,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct Affects {
+    #[serde(rename = "ref")]
+    bom_ref: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct Analysis<'a> {
+    state: &'static str,
+    detail: &'a str,
+}
+
+/// Render the report as a `CycloneDX` JSON SBOM.
+///
+/// Every lockfile package and every affected crate is a component, and every
+/// advisory a vulnerability affecting it. Ignored advisories are marked
+/// `not_affected`, with the reason they were ignored as the analysis detail.
+/// Yanked crates have no advisory and are left out.
+pub(crate) fn render(report: &Report<'_>) -> Result<String> {
+    let findings: Vec<&Finding> = report
+        .findings
+        .iter()
+        .filter(|finding| finding.rustsec.kind != AdvisoryKind::Yanked)
+        .collect();
+
+    let locked = report
+        .lockfile
+        .map(|lockfile| lockfile.packages.as_slice())
+        .unwrap_or_default()
+        .iter()
+        .map(|package| {
+            let purl = package.purl();
+            Component {
+                kind: "library",
+                // workspace and path packages have no purl
+                bom_ref: purl
+                    .clone()
+                    .unwrap_or_else(|| format!("{}@{}", package.name, package.version)),
+                name: &package.name,
+                version: &package.version,
+                purl,
+            }
+        });
+    // affected crates are components even without a lockfile, so every
+    // vulnerability refers to one
+    let affected = findings.iter().map(|Finding { rustsec, .. }| Component {
+        kind: "library",
+        bom_ref: purl(&rustsec.krate, &rustsec.version),
+        name: &rustsec.krate,
+        version: &rustsec.version,
+        purl: Some(purl(&rustsec.krate, &rustsec.version)),
+    });
+    let mut components: Vec<Component<'_>> = locked.chain(affected).collect();
+    // the same version may be locked from several sources
+    components.sort_by(|a, b| a.bom_ref.cmp(&b.bom_ref));
+    components.dedup_by(|a, b| a.bom_ref == b.bom_ref);
+
+    let vulnerabilities = findings
+        .into_iter()
+        .map(|Finding { rustsec, decision }| Vulnerability {
+            id: &rustsec.id,
+            source: Source {
+                name: "RustSec",
+                url: &rustsec.url,
+            },
+            description: &rustsec.title,
+            recommendation: &rustsec.solution,
+            advisories: vec![Advisory {
+                title: &rustsec.title,
+                url: &rustsec.url,
+            }],
+            affects: vec![Affects {
+                bom_ref: purl(&rustsec.krate, &rustsec.version),
+            }],
+            analysis: (decision.policy == Policy::Ignore).then_some(Analysis {
+                state: "not_affected",
+                detail: &decision.reason,
+            }),
+        })
+        .collect();
+
+    let bom = CycloneDx {
+        bom_format: "CycloneDX",
+        spec_version: SPEC_VERSION,
+        version: 1,
+        metadata: Metadata {
+            tools: Tools {
+                components: vec![Tool {
+                    kind: "application",
+                    name: env!("CARGO_PKG_NAME"),
+                    version: env!("CARGO_PKG_VERSION"),
+                }],
+            },
+        },
+        components,
+        vulnerabilities,
+    };
+    Ok(serde_json::to_string_pretty(&bom)?)
+}
+
+/// Write the `CycloneDX` SBOM to the given file.
+pub(crate) fn write(path: &str, report: &Report<'_>) -> Result<()> {
+    fs::write(path, render(report)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::render;
+    use crate::report::test::{TestReport, report};
+    use anyhow::Result;
+    use serde_json::Value;
+
+    #[test]
+    fn render_works() -> Result<()> {
+        let report = report(Some(("RUSTSEC-2020-0099", "aovec is only used in tests")))?;
+        let bom: Value = serde_json::from_str(&render(&report.as_report())?)?;
+
+        assert_eq!("CycloneDX", bom["bomFormat"]);
+        assert_eq!("1.5", bom["specVersion"]);
+        assert_eq!(Some(6), bom["components"].as_array().map(Vec::len));
+        // the workspace package is not published
+        assert_eq!("audit-check-test@0.1.0", bom["components"][0]["bom-ref"]);
+        assert!(bom["components"][0].get("purl").is_none());
+        assert_eq!("pkg:cargo/anymap@0.12.1", bom["components"][1]["purl"]);
+        assert_eq!(Some(4), bom["vulnerabilities"].as_array().map(Vec::len));
+        let aovec = &bom["vulnerabilities"][0];
+        assert_eq!("RUSTSEC-2020-0099", aovec["id"]);
+        assert_eq!("pkg:cargo/aovec@1.1.0", aovec["affects"][0]["ref"]);
+        assert_eq!("not_affected", aovec["analysis"]["state"]);
+        assert_eq!(
+            "ignored by '.github/audit-check.toml': aovec is only used in tests",
+            aovec["analysis"]["detail"]
+        );
+        assert!(bom["vulnerabilities"][1].get("analysis").is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn render_without_lockfile_works() -> Result<()> {
+        let empty = TestReport::empty(Ok(()));
+        let bom: Value = serde_json::from_str(&render(&empty.as_report())?)?;
+        assert_eq!(Some(0), bom["components"].as_array().map(Vec::len));
+        assert_eq!(Some(0), bom["vulnerabilities"].as_array().map(Vec::len));
+
+        let mut report = report(None)?;
+        report.lockfile = None;
+        let bom: Value = serde_json::from_str(&render(&report.as_report())?)?;
+        let components = bom["components"].as_array().cloned().unwrap_or_default();
+        assert_eq!(4, components.len());
+        // every vulnerability refers to a component
+        for vulnerability in bom["vulnerabilities"].as_array().into_iter().flatten() {
+            let bom_ref = &vulnerability["affects"][0]["ref"];
+            assert!(components.iter().any(|c| &c["bom-ref"] == bom_ref));
+        }
+        Ok(())
+    }
+}
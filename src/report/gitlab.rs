@@ -0,0 +1,254 @@
+// Copyright (c) 2023 audit-check developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or https://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use super::{Report, Status, timestamp};
+use crate::{
+    advisory::{AdvisoryKind, Rustsec},
+    cvss::Severity,
+    policy::Finding,
+};
+use anyhow::Result;
+use serde::Serialize;
+use std::fs;
+use time::OffsetDateTime;
+
+/// The GitLab security report schema version the report conforms to.
+const SCHEMA_VERSION: &str = "15.0.7";
+const LOCKFILE: &str = "Cargo.lock";
+
+#[derive(Clone, Debug, Serialize)]
+struct GitLabReport<'a> {
+    version: &'static str,
+    vulnerabilities: Vec<Vulnerability<'a>>,
+    scan: Scan<'a>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct Vulnerability<'a> {
+    id: String,
+    name: &'a str,
+    description: String,
+    severity: &'static str,
+    solution: &'a str,
+    identifiers: Vec<Identifier<'a>>,
+    links: Vec<Link<'a>>,
+    location: Location<'a>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct Identifier<'a> {
+    #[serde(rename = "type")]
+    kind: String,
+    name: &'a str,
+    value: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct Link<'a> {
+    url: &'a str,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct Location<'a> {
+    file: &'static str,
+    dependency: Dependency<'a>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct Dependency<'a> {
+    package: Package<'a>,
+    version: &'a str,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct Package<'a> {
+    name: &'a str,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct Scan<'a> {
+    analyzer: Tool<'a>,
+    scanner: Tool<'a>,
+    #[serde(rename = "type")]
+    kind: &'static str,
+    start_time: String,
+    end_time: String,
+    status: &'static str,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct Tool<'a> {
+    id: &'static str,
+    name: &'static str,
+    version: &'a str,
+    vendor: Vendor,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct Vendor {
+    name: &'static str,
+}
+
+/// Render the report as a GitLab dependency scanning report.
+///
+/// Ignored advisories and yanked crates are left out. The severity comes from
+/// the CVSS vector of an advisory, informational warnings without one are
+/// `Info`.
+pub(crate) fn render(report: &Report<'_>) -> Result<String> {
+    let gitlab = GitLabReport {
+        version: SCHEMA_VERSION,
+        vulnerabilities: report
+            .findings
+            .iter()
+            .filter(|finding| !finding.is_ignored() && finding.rustsec.kind != AdvisoryKind::Yanked)
+            .map(|Finding { rustsec, .. }| vulnerability(rustsec))
+            .collect(),
+        scan: Scan {
+            analyzer: Tool {
+                id: "audit-check",
+                name: env!("CARGO_PKG_NAME"),
+                version: env!("CARGO_PKG_VERSION"),
+                vendor: Vendor {
+                    name: env!("CARGO_PKG_NAME"),
+                },
+            },
+            scanner: Tool {
+                id: "cargo_audit",
+                name: "cargo audit",
+                version: &report.toolchain.cargo_audit,
+                vendor: Vendor { name: "RustSec" },
+            },
+            kind: "dependency_scanning",
+            start_time: timestamp(report.started),
+            end_time: timestamp(OffsetDateTime::now_utc()),
+            status: if report.status() == Status::Error {
+                "failure"
+            } else {
+                "success"
+            },
+        },
+    };
+    Ok(serde_json::to_string_pretty(&gitlab)?)
+}
+
+/// Write the GitLab dependency scanning report to the given file.
+pub(crate) fn write(path: &str, report: &Report<'_>) -> Result<()> {
+    fs::write(path, render(report)?)?;
+    Ok(())
+}
+
+fn vulnerability(rustsec: &Rustsec) -> Vulnerability<'_> {
+    let mut identifiers = vec![Identifier {
+        kind: "rustsec".to_string(),
+        name: &rustsec.id,
+        value: &rustsec.id,
+        url: Some(rustsec.url.clone()),
+    }];
+    identifiers.extend(rustsec.aliases.iter().map(|alias| alias_identifier(alias)));
+
+    Vulnerability {
+        // unique per advisory and affected crate version
+        id: format!("{}:{}@{}", rustsec.id, rustsec.krate, rustsec.version),
+        name: &rustsec.title,
+        description: format!(
+            "{} ({} in {} {})",
+            rustsec.title, rustsec.kind, rustsec.krate, rustsec.version
+        ),
+        severity: severity(rustsec),
+        solution: &rustsec.solution,
+        identifiers,
+        links: vec![Link { url: &rustsec.url }],
+        location: Location {
+            file: LOCKFILE,
+            dependency: Dependency {
+                package: Package {
+                    name: &rustsec.krate,
+                },
+                version: &rustsec.version,
+            },
+        },
+    }
+}
+
+// i.e. `CVE-2020-36206` or `GHSA-5wg8-7c9q-794v`
+fn alias_identifier(alias: &str) -> Identifier<'_> {
+    let prefix = alias.split('-').next().unwrap_or(alias).to_lowercase();
+    let url = match prefix.as_str() {
+        "cve" => Some(format!("https://nvd.nist.gov/vuln/detail/{alias}")),
+        "ghsa" => Some(format!("https://github.com/advisories/{alias}")),
+        _ => None,
+    };
+    Identifier {
+        kind: prefix,
+        name: alias,
+        value: alias,
+        url,
+    }
+}
+
+fn severity(rustsec: &Rustsec) -> &'static str {
+    match rustsec.severity() {
+        Some(Severity::Critical) => "Critical",
+        Some(Severity::High) => "High",
+        Some(Severity::Medium) => "Medium",
+        Some(Severity::Low) => "Low",
+        None if rustsec.kind == AdvisoryKind::Vulnerability => "Unknown",
+        Some(Severity::None) | None => "Info",
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::render;
+    use crate::{
+        error::AuditCheckError,
+        report::test::{TestReport, report},
+    };
+    use anyhow::Result;
+    use serde_json::Value;
+
+    #[test]
+    fn render_works() -> Result<()> {
+        let report = report(Some((
+            "RUSTSEC-2022-0040",
+            "owning_ref is only used in tests",
+        )))?;
+        let gitlab: Value = serde_json::from_str(&render(&report.as_report())?)?;
+
+        assert_eq!("15.0.7", gitlab["version"]);
+        assert_eq!("dependency_scanning", gitlab["scan"]["type"]);
+        assert_eq!("1970-01-01T00:00:00", gitlab["scan"]["start_time"]);
+        assert_eq!("success", gitlab["scan"]["status"]);
+        assert_eq!("0.21.2", gitlab["scan"]["scanner"]["version"]);
+        let vulnerabilities = gitlab["vulnerabilities"].as_array().map(Vec::len);
+        assert_eq!(Some(3), vulnerabilities);
+        let aovec = &gitlab["vulnerabilities"][0];
+        assert_eq!("RUSTSEC-2020-0099:aovec@1.1.0", aovec["id"]);
+        assert_eq!("High", aovec["severity"]);
+        assert_eq!("cve", aovec["identifiers"][1]["type"]);
+        assert_eq!(
+            "https://nvd.nist.gov/vuln/detail/CVE-2020-36206",
+            aovec["identifiers"][1]["url"]
+        );
+        assert_eq!("Cargo.lock", aovec["location"]["file"]);
+        assert_eq!("aovec", aovec["location"]["dependency"]["package"]["name"]);
+        assert_eq!("Info", gitlab["vulnerabilities"][2]["severity"]);
+        Ok(())
+    }
+
+    #[test]
+    fn render_tool_error_works() -> Result<()> {
+        let report = TestReport::empty(Err(AuditCheckError::AuditExit { code: 2 }.into()));
+        let gitlab: Value = serde_json::from_str(&render(&report.as_report())?)?;
+        assert_eq!("failure", gitlab["scan"]["status"]);
+        assert_eq!(Some(0), gitlab["vulnerabilities"].as_array().map(Vec::len));
+        Ok(())
+    }
+}
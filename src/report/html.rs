@@ -0,0 +1,195 @@
+// Copyright (c) 2023 audit-check developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or https://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use super::{Report, Status, timestamp};
+use crate::{
+    advisory::Rustsec,
+    policy::{Finding, Policy},
+    template::bundled,
+};
+use anyhow::Result;
+use minijinja::context;
+use serde::Serialize;
+use std::fs;
+
+const TEMPLATE: &str = include_str!("html/report.html");
+
+#[derive(Clone, Debug, Serialize)]
+struct Advisory<'a> {
+    #[serde(flatten)]
+    rustsec: &'a Rustsec,
+    /// The CVSS severity, `unknown` without a CVSS vector
+    severity: &'static str,
+    severity_rank: u8,
+    score: Option<f64>,
+    outcome: &'static str,
+    reason: &'a str,
+    tree: Vec<Node<'a>>,
+}
+
+// A crate in the dependency tree, with the crates depending on it as children.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+struct Node<'a> {
+    name: &'a str,
+    children: Vec<Node<'a>>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct RunResult {
+    status: Status,
+    message: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct Counts {
+    failed: usize,
+    warned: usize,
+    ignored: usize,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct Error<'a> {
+    kind: String,
+    message: &'a str,
+}
+
+/// Render the report as a self-contained HTML page.
+pub(crate) fn render(report: &Report<'_>) -> Result<String> {
+    let advisories: Vec<Advisory<'_>> = report
+        .findings
+        .iter()
+        .map(|Finding { rustsec, decision }| {
+            let severity = rustsec.severity();
+            Advisory {
+                rustsec,
+                severity: severity.map_or("unknown", |severity| severity.as_str()),
+                severity_rank: severity.map_or(0, |severity| severity as u8 + 1),
+                score: rustsec.score(),
+                outcome: decision.policy.outcome(),
+                reason: &decision.reason,
+                tree: tree(&rustsec.dependency_paths),
+            }
+        })
+        .collect();
+    let errors: Vec<Error<'_>> = report
+        .diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.is_error())
+        .map(|diagnostic| Error {
+            kind: diagnostic.kind.to_string(),
+            message: &diagnostic.message,
+        })
+        .collect();
+
+    let env = bundled(&[("report.html", TEMPLATE)])?;
+    Ok(env.get_template("report.html")?.render(context! {
+        tool => context! {
+            name => env!("CARGO_PKG_NAME"),
+            version => env!("CARGO_PKG_VERSION"),
+        },
+        started => timestamp(report.started).replace('T', " "),
+        result => RunResult {
+            status: report.status(),
+            message: report.result.as_ref().err().map(ToString::to_string),
+        },
+        counts => Counts {
+            failed: report.count(Policy::Fail),
+            warned: report.count(Policy::Warn),
+            ignored: report.count(Policy::Ignore),
+        },
+        advisories,
+        errors,
+        toolchain => report.toolchain,
+    })?)
+}
+
+/// Write the HTML report to the given file.
+pub(crate) fn write(path: &str, report: &Report<'_>) -> Result<()> {
+    fs::write(path, render(report)?)?;
+    Ok(())
+}
+
+// Merge the dependency paths, which all start at the affected crate, into a tree.
+fn tree(paths: &[Vec<String>]) -> Vec<Node<'_>> {
+    let mut roots: Vec<Node<'_>> = vec![];
+    for path in paths {
+        let mut nodes = &mut roots;
+        for name in path {
+            let idx = if let Some(idx) = nodes.iter().position(|node| node.name == name) {
+                idx
+            } else {
+                nodes.push(Node {
+                    name,
+                    children: vec![],
+                });
+                nodes.len() - 1
+            };
+            nodes = &mut nodes[idx].children;
+        }
+    }
+    roots
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Node, render, tree};
+    use crate::report::test::report;
+    use anyhow::Result;
+
+    #[test]
+    fn render_works() -> Result<()> {
+        let html = render(&report(None)?.as_report())?;
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("on 1970-01-01 00:00:00 UTC"));
+        assert!(
+            html.contains("Failed:\n  4 failed, 0 warned, 0 ignored &mdash; RUSTSEC issue found")
+        );
+        assert!(html.contains(r#"<span class="badge high">high 8.1</span>"#));
+        assert!(html.contains(
+            r#"RUSTSEC-2020-0099">RUSTSEC-2020-0099</a><br><small>CVE-2020-36206</small>"#
+        ));
+        // titles are escaped
+        assert!(html.contains("Aovec&lt;T&gt; lacks bound"));
+        assert!(html.contains("<summary>2 paths</summary>"));
+        assert!(html.contains("<script>"));
+        Ok(())
+    }
+
+    #[test]
+    fn tree_works() {
+        let paths = vec![
+            vec![
+                "smallvec 0.4.5".to_string(),
+                "aovec 1.1.0".to_string(),
+                "audit-check-test 0.1.0".to_string(),
+            ],
+            vec![
+                "smallvec 0.4.5".to_string(),
+                "audit-check-test 0.1.0".to_string(),
+            ],
+        ];
+        let leaf = |name| Node {
+            name,
+            children: vec![],
+        };
+        assert_eq!(
+            vec![Node {
+                name: "smallvec 0.4.5",
+                children: vec![
+                    Node {
+                        name: "aovec 1.1.0",
+                        children: vec![leaf("audit-check-test 0.1.0")],
+                    },
+                    leaf("audit-check-test 0.1.0"),
+                ],
+            }],
+            tree(&paths)
+        );
+    }
+}
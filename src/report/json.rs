@@ -0,0 +1,180 @@
+// Copyright (c) 2023 audit-check developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or https://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use super::{Report, Status};
+use crate::{
+    advisory::{Priority, Rustsec},
+    policy::{Finding, Policy},
+    toolchain::Toolchain,
+};
+use anyhow::Result;
+use serde::Serialize;
+use std::fs;
+
+/// The version of the report schema, bumped on any breaking change.
+pub(crate) const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Clone, Debug, Serialize)]
+struct JsonReport<'a> {
+    schema_version: u32,
+    tool: Tool,
+    toolchain: &'a Toolchain,
+    result: RunResult,
+    counts: Counts,
+    advisories: Vec<Advisory<'a>>,
+    errors: Vec<Error<'a>>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct Tool {
+    name: &'static str,
+    version: &'static str,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct RunResult {
+    status: Status,
+    exit_code: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct Counts {
+    failed: usize,
+    warned: usize,
+    ignored: usize,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct Advisory<'a> {
+    #[serde(flatten)]
+    rustsec: &'a Rustsec,
+    priority: Priority,
+    decision: Decision<'a>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct Decision<'a> {
+    /// failed, warned or ignored
+    outcome: &'static str,
+    reason: &'a str,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct Error<'a> {
+    kind: String,
+    message: &'a str,
+}
+
+/// Render the report as JSON, see the README for the schema.
+pub(crate) fn render(report: &Report<'_>) -> Result<String> {
+    let json = JsonReport {
+        schema_version: SCHEMA_VERSION,
+        tool: Tool {
+            name: env!("CARGO_PKG_NAME"),
+            version: env!("CARGO_PKG_VERSION"),
+        },
+        toolchain: report.toolchain,
+        result: RunResult {
+            status: report.status(),
+            exit_code: report.exit_code(),
+            message: report.result.as_ref().err().map(ToString::to_string),
+        },
+        counts: Counts {
+            failed: report.count(Policy::Fail),
+            warned: report.count(Policy::Warn),
+            ignored: report.count(Policy::Ignore),
+        },
+        advisories: report
+            .findings
+            .iter()
+            .map(|Finding { rustsec, decision }| Advisory {
+                rustsec,
+                priority: rustsec.kind.priority(),
+                decision: Decision {
+                    outcome: decision.policy.outcome(),
+                    reason: &decision.reason,
+                },
+            })
+            .collect(),
+        errors: report
+            .diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.is_error())
+            .map(|diagnostic| Error {
+                kind: diagnostic.kind.to_string(),
+                message: &diagnostic.message,
+            })
+            .collect(),
+    };
+    Ok(serde_json::to_string_pretty(&json)?)
+}
+
+/// Write the JSON report to the given file.
+pub(crate) fn write(path: &str, report: &Report<'_>) -> Result<()> {
+    fs::write(path, render(report)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{SCHEMA_VERSION, render};
+    use crate::{
+        diagnostic::Diagnostic,
+        error::AuditCheckError,
+        report::test::{TestReport, report},
+    };
+    use anyhow::Result;
+    use serde_json::Value;
+
+    #[test]
+    fn render_works() -> Result<()> {
+        let report = report(Some(("RUSTSEC-2020-0099", "aovec is only used in tests")))?;
+        let json: Value = serde_json::from_str(&render(&report.as_report())?)?;
+
+        assert_eq!(SCHEMA_VERSION, json["schema_version"]);
+        assert_eq!("audit-check", json["tool"]["name"]);
+        assert_eq!("0.21.2", json["toolchain"]["cargo_audit"]);
+        assert_eq!("failed", json["result"]["status"]);
+        assert_eq!(1, json["result"]["exit_code"]);
+        assert_eq!(3, json["counts"]["failed"]);
+        assert_eq!(1, json["counts"]["ignored"]);
+        let advisories = json["advisories"].as_array().map(Vec::len);
+        assert_eq!(Some(4), advisories);
+        let aovec = &json["advisories"][0];
+        assert_eq!("RUSTSEC-2020-0099", aovec["id"]);
+        assert_eq!("high", aovec["priority"]);
+        assert_eq!("ignored", aovec["decision"]["outcome"]);
+        assert_eq!(
+            "ignored by '.github/audit-check.toml': aovec is only used in tests",
+            aovec["decision"]["reason"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn render_tool_errors_works() -> Result<()> {
+        let mut report = TestReport::empty(Err(AuditCheckError::AuditFailed {
+            message: "error: couldn't fetch advisory database".to_string(),
+        }
+        .into()));
+        report.diagnostics = vec![Diagnostic::classify(
+            "error: couldn't fetch advisory database",
+        )];
+        let json: Value = serde_json::from_str(&render(&report.as_report())?)?;
+        assert_eq!("error", json["result"]["status"]);
+        assert_eq!(4, json["result"]["exit_code"]);
+        assert_eq!(
+            "cargo audit failed: error: couldn't fetch advisory database",
+            json["result"]["message"]
+        );
+        assert_eq!("advisory database error", json["errors"][0]["kind"]);
+        Ok(())
+    }
+}
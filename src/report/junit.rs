@@ -0,0 +1,155 @@
+// Copyright (c) 2023 audit-check developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or https://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use super::{Report, Status};
+use crate::policy::{Finding, Policy};
+use anyhow::Result;
+use std::{fmt::Write as _, fs};
+
+const SUITE: &str = "cargo audit";
+
+/// Render the report as `JUnit` XML.
+///
+/// Every advisory is a failed testcase and every ignored advisory a skipped
+/// one. A clean audit is a single passing testcase, and a check that could
+/// not be completed a single errored one.
+pub(crate) fn render(report: &Report<'_>) -> String {
+    let mut cases = String::new();
+    let (mut failures, mut errors, mut skipped) = (0, 0, 0);
+    for Finding { rustsec, decision } in report.findings {
+        let _ = writeln!(
+            cases,
+            r#"    <testcase classname="{}" name="{} {} {}">"#,
+            escape(&rustsec.krate),
+            escape(&rustsec.id),
+            escape(&rustsec.krate),
+            escape(&rustsec.version),
+        );
+        if decision.policy == Policy::Ignore {
+            skipped += 1;
+            let _ = writeln!(
+                cases,
+                r#"      <skipped message="{}"/>"#,
+                escape(&decision.reason)
+            );
+        } else {
+            failures += 1;
+            let _ = writeln!(
+                cases,
+                r#"      <failure message="{}: {}" type="{}">{}&#10;{}</failure>"#,
+                escape(&rustsec.title),
+                escape(&rustsec.url),
+                rustsec.kind,
+                escape(&rustsec.solution),
+                escape(&decision.reason),
+            );
+        }
+        cases.push_str("    </testcase>\n");
+    }
+    if report.status() == Status::Error {
+        errors += 1;
+        let message = report
+            .result
+            .as_ref()
+            .err()
+            .map(ToString::to_string)
+            .unwrap_or_default();
+        let _ = writeln!(
+            cases,
+            "    <testcase classname=\"{SUITE}\" name=\"{SUITE}\">\n      <error message=\"{}\"/>\n    </testcase>",
+            escape(&message)
+        );
+    } else if report.findings.is_empty() {
+        let _ = writeln!(
+            cases,
+            "    <testcase classname=\"{SUITE}\" name=\"{SUITE}\"/>"
+        );
+    }
+    let tests = report.findings.len().max(1);
+
+    let mut xml = r#"<?xml version="1.0" encoding="UTF-8"?>"#.to_string();
+    xml.push('\n');
+    let _ = writeln!(
+        xml,
+        r#"<testsuites name="{SUITE}" tests="{tests}" failures="{failures}" errors="{errors}" skipped="{skipped}">"#
+    );
+    let _ = writeln!(
+        xml,
+        r#"  <testsuite name="{SUITE}" tests="{tests}" failures="{failures}" errors="{errors}" skipped="{skipped}">"#
+    );
+    xml.push_str(&cases);
+    xml.push_str("  </testsuite>\n</testsuites>\n");
+    xml
+}
+
+/// Write the `JUnit` XML report to the given file.
+pub(crate) fn write(path: &str, report: &Report<'_>) -> Result<()> {
+    fs::write(path, render(report))?;
+    Ok(())
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod test {
+    use super::{escape, render};
+    use crate::{
+        error::AuditCheckError,
+        report::test::{TestReport, report},
+    };
+    use anyhow::Result;
+
+    #[test]
+    fn render_works() -> Result<()> {
+        let report = report(Some(("RUSTSEC-2020-0099", "aovec is only used in tests")))?;
+        let xml = render(&report.as_report());
+        assert!(xml.contains(
+            r#"<testsuite name="cargo audit" tests="4" failures="3" errors="0" skipped="1">"#
+        ));
+        assert!(xml.contains(
+            r#"<testcase classname="aovec" name="RUSTSEC-2020-0099 aovec 1.1.0">
+      <skipped message="ignored by &apos;.github/audit-check.toml&apos;: aovec is only used in tests"/>"#
+        ));
+        assert!(xml.contains(
+            r#"<failure message="Multiple soundness issues in `owning_ref`: https://rustsec.org/advisories/RUSTSEC-2022-0040" type="vulnerability">"#
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn render_clean_audit_works() {
+        let xml = render(&TestReport::empty(Ok(())).as_report());
+        assert!(xml.contains(r#"tests="1" failures="0" errors="0" skipped="0""#));
+        assert!(xml.contains(r#"<testcase classname="cargo audit" name="cargo audit"/>"#));
+    }
+
+    #[test]
+    fn render_tool_error_works() {
+        let report = TestReport::empty(Err(AuditCheckError::AuditExit { code: 2 }.into()));
+        let xml = render(&report.as_report());
+        assert!(xml.contains(r#"tests="1" failures="0" errors="1" skipped="0""#));
+        assert!(xml.contains(
+            r#"<error message="cargo audit exited with code 2 without reporting a denied advisory"/>"#
+        ));
+    }
+
+    #[test]
+    fn escape_works() {
+        assert_eq!(
+            "&lt;a href=&quot;x&quot;&gt;&amp;&apos;&lt;/a&gt;",
+            escape(r#"<a href="x">&'</a>"#)
+        );
+    }
+}
@@ -0,0 +1,194 @@
+// Copyright (c) 2023 audit-check developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or https://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use crate::{
+    advisory::Rustsec,
+    diagnostic::Diagnostic,
+    error::{EXIT_ADVISORIES, EXIT_WARNINGS, exit_code},
+    lockfile::Lockfile,
+    policy::{Finding, Policy},
+    toolchain::Toolchain,
+};
+use anyhow::Result;
+use serde::Serialize;
+use time::{OffsetDateTime, UtcOffset};
+
+pub(crate) mod cyclonedx;
+pub(crate) mod gitlab;
+pub(crate) mod html;
+pub(crate) mod json;
+pub(crate) mod junit;
+pub(crate) mod osv;
+
+/// Everything a check produced, the input to every report.
+#[derive(Debug)]
+pub(crate) struct Report<'a> {
+    pub(crate) findings: &'a [Finding],
+    pub(crate) diagnostics: &'a [Diagnostic],
+    pub(crate) toolchain: &'a Toolchain,
+    /// The audited lockfile, when it could be read
+    pub(crate) lockfile: Option<&'a Lockfile>,
+    /// When the check started
+    pub(crate) started: OffsetDateTime,
+    pub(crate) result: &'a Result<()>,
+}
+
+/// The overall result of a check.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Status {
+    /// No advisory failed the policies
+    Passed,
+    /// Advisories failed the policies
+    Failed,
+    /// The check could not be completed
+    Error,
+}
+
+impl Report<'_> {
+    /// The process exit code for this check.
+    pub(crate) fn exit_code(&self) -> u8 {
+        self.result.as_ref().map_or_else(exit_code, |()| 0)
+    }
+
+    pub(crate) fn status(&self) -> Status {
+        match self.exit_code() {
+            0 => Status::Passed,
+            EXIT_ADVISORIES | EXIT_WARNINGS => Status::Failed,
+            _ => Status::Error,
+        }
+    }
+
+    /// The advisories that were not ignored.
+    pub(crate) fn advisories(&self) -> Vec<Rustsec> {
+        self.findings
+            .iter()
+            .filter(|finding| !finding.is_ignored())
+            .map(|finding| finding.rustsec.clone())
+            .collect()
+    }
+
+    /// The number of advisories with the given policy applied.
+    pub(crate) fn count(&self, policy: Policy) -> usize {
+        self.findings
+            .iter()
+            .filter(|finding| finding.decision.policy == policy)
+            .count()
+    }
+}
+
+/// The given time in UTC, i.e. `2023-03-01T10:00:00`.
+pub(crate) fn timestamp(datetime: OffsetDateTime) -> String {
+    let datetime = datetime.to_offset(UtcOffset::UTC);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        datetime.year(),
+        u8::from(datetime.month()),
+        datetime.day(),
+        datetime.hour(),
+        datetime.minute(),
+        datetime.second()
+    )
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::{Finding, Report, Status};
+    use crate::{
+        advisory::{add_dependency_paths, parse, test::TEST_REPORT},
+        config::{Ignore, test::config},
+        diagnostic::Diagnostic,
+        error::AuditCheckError,
+        lockfile::{Lockfile, test::TEST_LOCKFILE},
+        policy::{evaluate, outcome},
+        toolchain::{Toolchain, test::toolchain},
+    };
+    use anyhow::Result;
+    use time::OffsetDateTime;
+
+    /// The owned parts of a [`Report`], which only borrows them.
+    pub(crate) struct TestReport {
+        pub(crate) findings: Vec<Finding>,
+        pub(crate) diagnostics: Vec<Diagnostic>,
+        pub(crate) toolchain: Toolchain,
+        pub(crate) lockfile: Option<Lockfile>,
+        pub(crate) result: Result<()>,
+    }
+
+    impl TestReport {
+        /// A report without findings or a lockfile, as for a clean audit or
+        /// a tool error.
+        pub(crate) fn empty(result: Result<()>) -> Self {
+            Self {
+                findings: vec![],
+                diagnostics: vec![],
+                toolchain: toolchain(),
+                lockfile: None,
+                result,
+            }
+        }
+
+        pub(crate) fn as_report(&self) -> Report<'_> {
+            Report {
+                findings: &self.findings,
+                diagnostics: &self.diagnostics,
+                toolchain: &self.toolchain,
+                lockfile: self.lockfile.as_ref(),
+                started: OffsetDateTime::UNIX_EPOCH,
+                result: &self.result,
+            }
+        }
+    }
+
+    /// The report on [`TEST_REPORT`] and [`TEST_LOCKFILE`], with the
+    /// `(id, reason)` advisory ignored in the config file.
+    pub(crate) fn report(ignored: Option<(&str, &str)>) -> Result<TestReport> {
+        let mut config = config();
+        if let Some((id, reason)) = ignored {
+            config.ignore.push(Ignore {
+                id: id.to_string(),
+                reason: reason.to_string(),
+                source: ".github/audit-check.toml".to_string(),
+            });
+        }
+        let lockfile = Lockfile::parse(TEST_LOCKFILE)?;
+        let mut rustsecs = parse(TEST_REPORT)?;
+        add_dependency_paths(&mut rustsecs, &lockfile);
+        let findings = evaluate(&config, rustsecs);
+        let result = outcome(&findings);
+        Ok(TestReport {
+            findings,
+            diagnostics: vec![],
+            toolchain: toolchain(),
+            lockfile: Some(lockfile),
+            result,
+        })
+    }
+
+    #[test]
+    fn status_works() {
+        let status = |result: Result<()>| {
+            let test = TestReport::empty(result);
+            let report = test.as_report();
+            (report.status(), report.exit_code())
+        };
+        assert_eq!((Status::Passed, 0), status(Ok(())));
+        assert_eq!(
+            (Status::Failed, 1),
+            status(Err(AuditCheckError::RustSec.into()))
+        );
+        assert_eq!(
+            (Status::Failed, 7),
+            status(Err(AuditCheckError::Warnings.into()))
+        );
+        assert_eq!(
+            (Status::Error, 4),
+            status(Err(AuditCheckError::AuditExit { code: 2 }.into()))
+        );
+    }
+}
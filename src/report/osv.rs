@@ -0,0 +1,417 @@
+// Copyright (c) 2023 audit-check developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or https://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use super::{Report, timestamp};
+use crate::{
+    advisory::{AdvisoryKind, Versions},
+    lockfile::purl,
+    policy::Finding,
+};
+use anyhow::Result;
+use rustc_version::Version;
+use serde::Serialize;
+use std::{cmp, fs};
+
+const SCHEMA_VERSION: &str = "1.6.0";
+const ECOSYSTEM: &str = "crates.io";
+
+#[derive(Clone, Debug, Serialize)]
+struct Osv<'a> {
+    schema_version: &'static str,
+    id: &'a str,
+    modified: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    published: Option<String>,
+    aliases: &'a [String],
+    summary: &'a str,
+    affected: Vec<Affected<'a>>,
+    references: Vec<Reference<'a>>,
+    database_specific: DatabaseSpecific<'a>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct Affected<'a> {
+    package: Package<'a>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    ranges: Vec<Range>,
+    versions: Vec<&'a str>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct Package<'a> {
+    ecosystem: &'static str,
+    name: &'a str,
+    purl: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct Range {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    events: Vec<Event>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Event {
+    Introduced(String),
+    Fixed(String),
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct Reference<'a> {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    url: &'a str,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct DatabaseSpecific<'a> {
+    kind: AdvisoryKind,
+    /// failed, warned or ignored
+    outcome: &'static str,
+    reason: &'a str,
+}
+
+/// Render the advisories as a JSON array of OSV entries.
+///
+/// Yanked crates have no advisory and are left out. The affected ranges are
+/// derived from the patched and unaffected versions of each advisory, and
+/// omitted when those cannot be read.
+pub(crate) fn render(report: &Report<'_>) -> Result<String> {
+    let osvs: Vec<Osv<'_>> = report
+        .findings
+        .iter()
+        .filter(|finding| finding.rustsec.kind != AdvisoryKind::Yanked)
+        .map(|Finding { rustsec, decision }| {
+            let published = published(&rustsec.date);
+            // cargo audit doesn't report when an advisory last changed, the
+            // database update is the closest we know of
+            let modified = report
+                .toolchain
+                .advisory_db
+                .as_ref()
+                .and_then(|database| database.last_updated.clone())
+                .or_else(|| published.clone())
+                .unwrap_or_else(|| format!("{}Z", timestamp(report.started)));
+            Osv {
+                schema_version: SCHEMA_VERSION,
+                id: &rustsec.id,
+                modified,
+                published,
+                aliases: &rustsec.aliases,
+                summary: &rustsec.title,
+                affected: vec![Affected {
+                    package: Package {
+                        ecosystem: ECOSYSTEM,
+                        name: &rustsec.krate,
+                        purl: purl(&rustsec.krate, &rustsec.version),
+                    },
+                    ranges: ranges(&rustsec.versions).into_iter().collect(),
+                    versions: vec![&rustsec.version],
+                }],
+                references: vec![Reference {
+                    kind: "ADVISORY",
+                    url: &rustsec.url,
+                }],
+                database_specific: DatabaseSpecific {
+                    kind: rustsec.kind,
+                    outcome: decision.policy.outcome(),
+                    reason: &decision.reason,
+                },
+            }
+        })
+        .collect();
+    Ok(serde_json::to_string_pretty(&osvs)?)
+}
+
+// The text report may lack the `YYYY-MM-DD` date, i.e. `No Date`, and OSV
+// only takes RFC 3339 timestamps.
+fn published(date: &str) -> Option<String> {
+    let valid = date.len() == 10
+        && date.bytes().enumerate().all(|(i, b)| match i {
+            4 | 7 => b == b'-',
+            _ => b.is_ascii_digit(),
+        });
+    valid.then(|| format!("{date}T00:00:00Z"))
+}
+
+/// Write the OSV export to the given file.
+pub(crate) fn write(path: &str, report: &Report<'_>) -> Result<()> {
+    fs::write(path, render(report)?)?;
+    Ok(())
+}
+
+// The affected versions are every version neither patched nor unaffected.
+fn ranges(versions: &Versions) -> Option<Range> {
+    let mut safe = versions
+        .patched
+        .iter()
+        .chain(&versions.unaffected)
+        .map(|req| requirement(req))
+        .collect::<Option<Vec<_>>>()?;
+    safe.retain(|interval| !interval.is_empty());
+    safe.sort_by(|a, b| a.start.cmp(&b.start));
+
+    let mut events = vec![];
+    let mut start = Some(Version::new(0, 0, 0));
+    for interval in safe {
+        let Some(current) = start else {
+            break;
+        };
+        if interval.start > current {
+            events.push(introduced(&current));
+            events.push(Event::Fixed(interval.start.to_string()));
+        }
+        start = interval.end.map(|end| cmp::max(current, end));
+    }
+    if let Some(current) = start {
+        events.push(introduced(&current));
+    }
+    Some(Range {
+        kind: "SEMVER",
+        events,
+    })
+}
+
+fn introduced(version: &Version) -> Event {
+    if *version == Version::new(0, 0, 0) {
+        Event::Introduced("0".to_string())
+    } else {
+        Event::Introduced(version.to_string())
+    }
+}
+
+// The versions from `start` up to, but excluding, `end`, without an end
+// every later version is included.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Interval {
+    start: Version,
+    end: Option<Version>,
+}
+
+impl Interval {
+    fn is_empty(&self) -> bool {
+        self.end.as_ref().is_some_and(|end| *end <= self.start)
+    }
+
+    fn intersect(self, other: Self) -> Self {
+        Self {
+            start: cmp::max(self.start, other.start),
+            end: match (self.end, other.end) {
+                (Some(a), Some(b)) => Some(cmp::min(a, b)),
+                (a, b) => a.or(b),
+            },
+        }
+    }
+}
+
+// A requirement such as `^0.3.4, <0.4.0`, every comparator must match.
+fn requirement(req: &str) -> Option<Interval> {
+    req.split(',').try_fold(
+        Interval {
+            start: Version::new(0, 0, 0),
+            end: None,
+        },
+        |interval, comparator| Some(interval.intersect(self::comparator(comparator)?)),
+    )
+}
+
+fn comparator(comparator: &str) -> Option<Interval> {
+    let comparator = comparator.trim();
+    let (op, version) = [">=", "<=", ">", "<", "=", "^", "~"]
+        .iter()
+        .find_map(|op| {
+            comparator
+                .strip_prefix(op)
+                .map(|version| (*op, version.trim()))
+        })
+        .unwrap_or(("^", comparator));
+    let partial = Partial::parse(version)?;
+    let zero = Version::new(0, 0, 0);
+    let (start, end) = match op {
+        ">=" => (partial.version(), None),
+        ">" => (partial.bump_last(), None),
+        "<" => (zero, Some(partial.version())),
+        "<=" => (zero, Some(partial.bump_last())),
+        "=" => (partial.version(), Some(partial.bump_last())),
+        "~" => (partial.version(), Some(partial.bump_tilde())),
+        _ => (partial.version(), Some(partial.bump_caret())),
+    };
+    Some(Interval { start, end })
+}
+
+// A version with optional minor and patch parts, i.e. the `0.3` in `^0.3`.
+// Pre-releases are not supported.
+#[derive(Clone, Copy, Debug)]
+struct Partial {
+    major: u64,
+    minor: Option<u64>,
+    patch: Option<u64>,
+}
+
+impl Partial {
+    fn parse(version: &str) -> Option<Self> {
+        let mut parts = version.split('.').map(|part| part.parse::<u64>().ok());
+        let partial = Self {
+            major: parts.next()??,
+            minor: parts.next().map_or(Some(None), |part| part.map(Some))?,
+            patch: parts.next().map_or(Some(None), |part| part.map(Some))?,
+        };
+        parts.next().is_none().then_some(partial)
+    }
+
+    fn version(self) -> Version {
+        Version::new(
+            self.major,
+            self.minor.unwrap_or_default(),
+            self.patch.unwrap_or_default(),
+        )
+    }
+
+    fn bump_last(self) -> Version {
+        match (self.minor, self.patch) {
+            (None, _) => Version::new(self.major + 1, 0, 0),
+            (Some(minor), None) => Version::new(self.major, minor + 1, 0),
+            (Some(minor), Some(patch)) => Version::new(self.major, minor, patch + 1),
+        }
+    }
+
+    fn bump_tilde(self) -> Version {
+        match self.minor {
+            None => Version::new(self.major + 1, 0, 0),
+            Some(minor) => Version::new(self.major, minor + 1, 0),
+        }
+    }
+
+    // the left-most non-zero part may not change
+    fn bump_caret(self) -> Version {
+        match (self.major, self.minor, self.patch) {
+            (0, Some(0), Some(patch)) => Version::new(0, 0, patch + 1),
+            (0, Some(minor), _) => Version::new(0, minor + 1, 0),
+            (major, _, _) => Version::new(major + 1, 0, 0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Event, Interval, ranges, render, requirement};
+    use crate::{
+        advisory::{Versions, parse, test::TEST_RUSTSEC},
+        config::test::config,
+        policy::evaluate,
+        report::test::{TestReport, report},
+    };
+    use anyhow::Result;
+    use rustc_version::Version;
+    use serde_json::Value;
+
+    fn versions(patched: &[&str], unaffected: &[&str]) -> Versions {
+        Versions {
+            patched: patched.iter().map(ToString::to_string).collect(),
+            unaffected: unaffected.iter().map(ToString::to_string).collect(),
+        }
+    }
+
+    fn events(patched: &[&str], unaffected: &[&str]) -> Option<Vec<Event>> {
+        ranges(&versions(patched, unaffected)).map(|range| range.events)
+    }
+
+    #[test]
+    fn render_works() -> Result<()> {
+        let osvs: Value = serde_json::from_str(&render(&report(None)?.as_report())?)?;
+
+        assert_eq!(Some(4), osvs.as_array().map(Vec::len));
+        let aovec = &osvs[0];
+        assert_eq!("RUSTSEC-2020-0099", aovec["id"]);
+        assert_eq!("2020-12-10T00:00:00Z", aovec["published"]);
+        assert_eq!("2023-03-01T10:00:00Z", aovec["modified"]);
+        assert_eq!("CVE-2020-36206", aovec["aliases"][0]);
+        assert_eq!("crates.io", aovec["affected"][0]["package"]["ecosystem"]);
+        assert_eq!("aovec", aovec["affected"][0]["package"]["name"]);
+        assert_eq!(
+            serde_json::json!([{ "type": "SEMVER", "events": [{ "introduced": "0" }] }]),
+            aovec["affected"][0]["ranges"]
+        );
+        assert_eq!("1.1.0", aovec["affected"][0]["versions"][0]);
+        assert_eq!("failed", aovec["database_specific"]["outcome"]);
+        Ok(())
+    }
+
+    #[test]
+    fn render_text_report_works() -> Result<()> {
+        // the text report of an advisory without a date
+        let output = TEST_RUSTSEC.replace("Date:      2020-12-10\n", "");
+        let mut report = TestReport::empty(Ok(()));
+        report.findings = evaluate(&config(), parse(&output)?);
+        report.toolchain.advisory_db = None;
+        let osvs: Value = serde_json::from_str(&render(&report.as_report())?)?;
+
+        let aovec = &osvs[0];
+        assert_eq!("RUSTSEC-2020-0099", aovec["id"]);
+        assert!(aovec.get("published").is_none());
+        assert_eq!("1970-01-01T00:00:00Z", aovec["modified"]);
+        let smallvec = osvs
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|osv| osv["id"] == "RUSTSEC-2018-0018");
+        assert_eq!(
+            Some("2018-09-25T00:00:00Z"),
+            smallvec.and_then(|osv| osv["published"].as_str())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn ranges_works() {
+        assert_eq!(
+            Some(vec![Event::Introduced("0".to_string())]),
+            events(&[], &[])
+        );
+        assert_eq!(
+            Some(vec![
+                Event::Introduced("0".to_string()),
+                Event::Fixed("1.2.3".to_string())
+            ]),
+            events(&[">=1.2.3"], &[])
+        );
+        assert_eq!(
+            Some(vec![
+                Event::Introduced("0.3.2".to_string()),
+                Event::Fixed("0.3.4".to_string()),
+                Event::Introduced("0.4.0".to_string()),
+                Event::Fixed("0.6.3".to_string()),
+            ]),
+            events(&[">=0.6.3", "^0.3.4, <0.4.0"], &["<0.3.2"])
+        );
+        assert_eq!(None, events(&[">=1.0.0-alpha.1"], &[]));
+    }
+
+    #[test]
+    fn requirement_works() {
+        let interval = |start: (u64, u64, u64), end: Option<(u64, u64, u64)>| {
+            Some(Interval {
+                start: Version::new(start.0, start.1, start.2),
+                end: end.map(|end| Version::new(end.0, end.1, end.2)),
+            })
+        };
+        assert_eq!(interval((1, 2, 3), Some((2, 0, 0))), requirement("1.2.3"));
+        assert_eq!(interval((0, 3, 0), Some((0, 4, 0))), requirement("^0.3"));
+        assert_eq!(interval((0, 0, 3), Some((0, 0, 4))), requirement("^0.0.3"));
+        assert_eq!(interval((1, 2, 0), Some((1, 3, 0))), requirement("~1.2"));
+        assert_eq!(interval((1, 2, 4), None), requirement("> 1.2.3"));
+        assert_eq!(interval((0, 0, 0), Some((1, 3, 0))), requirement("<=1.2"));
+        assert_eq!(
+            interval((0, 3, 4), Some((0, 4, 0))),
+            requirement("^0.3.4, <0.4.0")
+        );
+        assert_eq!(None, requirement("*"));
+    }
+}
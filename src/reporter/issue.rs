@@ -0,0 +1,270 @@
+// Copyright (c) 2023 audit-check developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or https://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use super::{Reporter, ReporterFuture};
+use crate::{
+    advisory::{AdvisoryKind, Rustsec},
+    config::Config,
+    error::AuditCheckError,
+    github::{api_headers, send_with_retry},
+    report::{Report, Status},
+    template::render,
+    toolchain::Toolchain,
+};
+use anyhow::Result;
+use reqwest::{Client, Version};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeSet, fs};
+use tracing::info;
+
+/// Creates a GitHub issue listing the advisories when the check fails, or
+/// only renders it on a dry run.
+#[derive(Clone, Debug)]
+pub(crate) struct IssueReporter<'a> {
+    pub(crate) client: Client,
+    pub(crate) config: &'a Config,
+}
+
+impl Reporter for IssueReporter<'_> {
+    fn name(&self) -> &'static str {
+        "issue"
+    }
+
+    fn report<'a>(&'a self, report: &'a Report<'a>) -> ReporterFuture<'a> {
+        Box::pin(async move {
+            if report.status() != Status::Failed {
+                return Ok(());
+            }
+            let rustsecs = report.advisories();
+            let issue = render_issue(self.config, &rustsecs, report.toolchain)?;
+            if self.config.dry_run {
+                dry_run(self.config, &issue)
+            } else {
+                let resp = create_issue(&self.client, self.config, &issue).await?;
+                info!("Issue {} created", resp.id);
+                Ok(())
+            }
+        })
+    }
+
+    // the issue is what the action is run for, so a failure to create it
+    // fails the check with the GitHub exit code
+    fn fails_check(&self) -> bool {
+        true
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct Issue {
+    title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    body: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    milestone: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    labels: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    assignees: Option<Vec<String>>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct Resp {
+    id: usize,
+}
+
+fn render_issue(config: &Config, rustsecs: &[Rustsec], toolchain: &Toolchain) -> Result<Issue> {
+    let title = match &config.title_template {
+        Some(template) => render("issue_title_template", template, rustsecs, toolchain)?,
+        None => default_title(rustsecs),
+    };
+    let body = match &config.body_template {
+        Some(template) => render("issue_body_template", template, rustsecs, toolchain)?,
+        None => default_body(rustsecs, toolchain),
+    };
+
+    // labelled with the highest priority, so unmaintained crates rank below vulnerabilities
+    let labels = rustsecs
+        .iter()
+        .map(|rustsec| rustsec.kind.priority())
+        .max()
+        .map(|priority| vec![format!("priority: {}", priority.as_str())]);
+
+    Ok(Issue {
+        title,
+        body: Some(body),
+        milestone: None,
+        labels,
+        assignees: None,
+    })
+}
+
+fn dry_run(config: &Config, issue: &Issue) -> Result<()> {
+    let payload = serde_json::to_string_pretty(issue)?;
+    info!("Dry run, the following issue would be created");
+    info!("{payload}");
+    fs::write(&config.dry_run_file, payload)?;
+    info!("Issue payload written to '{}'", config.dry_run_file);
+    Ok(())
+}
+
+async fn create_issue(client: &Client, config: &Config, issue: &Issue) -> Result<Resp> {
+    let token = config
+        .token
+        .as_deref()
+        .ok_or(AuditCheckError::MissingInput { input: "token" })?;
+    let owner_repo = config
+        .owner_repo
+        .as_deref()
+        .ok_or(AuditCheckError::MissingInput {
+            input: "GITHUB_REPOSITORY",
+        })?;
+
+    let url = format!(
+        "{}/repos/{owner_repo}/issues",
+        config.api_url.trim_end_matches('/')
+    );
+    let request = client
+        .post(&url)
+        .version(Version::HTTP_11)
+        .headers(api_headers())
+        .bearer_auth(token)
+        .json(issue);
+    let res = send_with_retry(request).await?;
+
+    if res.status() == 201 {
+        Ok(res.json::<Resp>().await?)
+    } else {
+        Err(AuditCheckError::CreateIssue.into())
+    }
+}
+
+/// The title used without an `issue_title_template`, the advisory ids.
+pub(crate) fn default_title(rustsecs: &[Rustsec]) -> String {
+    let ids: BTreeSet<&str> = rustsecs.iter().map(|rustsec| rustsec.id.as_str()).collect();
+    ids.into_iter().collect::<Vec<&str>>().join(", ")
+}
+
+/// The body used without an `issue_body_template`, the advisories followed
+/// by the toolchain.
+pub(crate) fn default_body(rustsecs: &[Rustsec], toolchain: &Toolchain) -> String {
+    format!("{}---\n\n{}", generate_body(rustsecs), toolchain.markdown())
+}
+
+fn generate_body(rustsecs: &[Rustsec]) -> String {
+    rustsecs.iter().fold(String::new(), |acc, rustsec| {
+        acc + &format!(
+            "# ‼️ {} ‼️\n{}\n\n````\n{}\n````\n\n",
+            rustsec.id,
+            rustsec.url,
+            describe(rustsec)
+        )
+    })
+}
+
+// Mirrors the plain text output of `cargo audit`
+fn describe(rustsec: &Rustsec) -> String {
+    let mut lines = vec![
+        format!("Crate:     {}", rustsec.krate),
+        format!("Version:   {}", rustsec.version),
+    ];
+    if rustsec.kind != AdvisoryKind::Vulnerability {
+        lines.push(format!("Warning:   {}", rustsec.kind));
+    }
+    lines.push(format!("Title:     {}", rustsec.title));
+    lines.push(format!("Date:      {}", rustsec.date));
+    lines.push(format!("ID:        {}", rustsec.id));
+    lines.push(format!("URL:       {}", rustsec.url));
+    lines.push(format!("Solution:  {}", rustsec.solution));
+    if !rustsec.dependency_paths.is_empty() {
+        lines.push("Dependency paths:".to_string());
+        for path in &rustsec.dependency_paths {
+            lines.push(path.join(" <- "));
+        }
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod test {
+    use super::{default_title, generate_body, render_issue};
+    use crate::{
+        advisory::{AdvisoryKind, parse, test::TEST_REPORT},
+        config::test::config,
+        toolchain::test::toolchain,
+    };
+    use anyhow::Result;
+
+    #[test]
+    fn generate_title_works() -> Result<()> {
+        let rustsecs = parse(TEST_REPORT)?;
+        assert_eq!(
+            "RUSTSEC-2018-0018, RUSTSEC-2020-0099, RUSTSEC-2021-0065, RUSTSEC-2022-0040",
+            default_title(&rustsecs)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn generate_body_works() -> Result<()> {
+        let rustsecs = parse(TEST_REPORT)?;
+        let body = generate_body(&rustsecs);
+        assert!(body.starts_with(
+            "# ‼️ RUSTSEC-2018-0018 ‼️\nhttps://rustsec.org/advisories/RUSTSEC-2018-0018\n\n````\nCrate:     smallvec\nVersion:   0.4.5\nWarning:   unsound\n"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn render_issue_works() -> Result<()> {
+        let rustsecs = parse(TEST_REPORT)?;
+        let issue = render_issue(&config(), &rustsecs, &toolchain())?;
+        assert_eq!(default_title(&rustsecs), issue.title);
+        assert_eq!(
+            Some(format!(
+                "{}---\n\n{}",
+                generate_body(&rustsecs),
+                toolchain().markdown()
+            )),
+            issue.body
+        );
+        assert_eq!(Some(vec!["priority: high".to_string()]), issue.labels);
+        Ok(())
+    }
+
+    #[test]
+    fn render_issue_labels_by_priority() -> Result<()> {
+        let rustsecs: Vec<_> = parse(TEST_REPORT)?
+            .into_iter()
+            .filter(|rustsec| rustsec.kind == AdvisoryKind::Unmaintained)
+            .collect();
+        let issue = render_issue(&config(), &rustsecs, &toolchain())?;
+        assert_eq!(Some(vec!["priority: low".to_string()]), issue.labels);
+        assert!(render_issue(&config(), &[], &toolchain())?.labels.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn render_issue_uses_templates() -> Result<()> {
+        let rustsecs = parse(TEST_REPORT)?;
+        let mut config = config();
+        config.title_template = Some("{{ advisories | length }} advisories found".to_string());
+        config.body_template = Some(
+            "{% for a in advisories %}- {{ a.crate }} {{ a.version }}\n{% endfor %}".to_string(),
+        );
+        let issue = render_issue(&config, &rustsecs, &toolchain())?;
+        assert_eq!("4 advisories found", issue.title);
+        assert_eq!(
+            Some(
+                "- smallvec 0.4.5\n- aovec 1.1.0\n- anymap 0.12.1\n- owning_ref 0.3.3\n"
+                    .to_string()
+            ),
+            issue.body
+        );
+        Ok(())
+    }
+}
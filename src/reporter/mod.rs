@@ -0,0 +1,323 @@
+// Copyright (c) 2023 audit-check developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or https://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use crate::{
+    config::{Config, Email, Webhook},
+    email,
+    github::client,
+    report::{Report, Status, cyclonedx, gitlab, html, json, junit, osv},
+    summary,
+    webhook::notify_changes,
+};
+use anyhow::{Context as _, Result};
+use reqwest::Client;
+use std::pin::Pin;
+use tracing::{error, info};
+
+use self::issue::IssueReporter;
+
+pub(crate) mod issue;
+
+/// The future returned by [`Reporter::report`].
+pub(crate) type ReporterFuture<'a> = Pin<Box<dyn Future<Output = Result<()>> + 'a>>;
+
+/// An output the result of a check is reported to, i.e. a report file, a
+/// GitHub issue or a webhook.
+pub(crate) trait Reporter {
+    /// The name failures are logged with.
+    fn name(&self) -> &'static str;
+
+    /// Report the result of the check, reporters decide for themselves
+    /// whether it is worth reporting.
+    fn report<'a>(&'a self, report: &'a Report<'a>) -> ReporterFuture<'a>;
+
+    /// Whether a failure fails the check rather than only being logged.
+    fn fails_check(&self) -> bool {
+        false
+    }
+}
+
+/// The reporters enabled by the configuration, the job summary is always
+/// written.
+pub(crate) fn select(config: &Config) -> Result<Vec<Box<dyn Reporter + '_>>> {
+    let outputs = &config.outputs;
+    let files: [(&'static str, &'static str, &Option<String>, Write); 6] = [
+        ("json", "Report", &outputs.report_file, json::write),
+        ("junit", "JUnit report", &outputs.junit_file, junit::write),
+        ("sbom", "SBOM", &outputs.sbom_file, cyclonedx::write),
+        ("osv", "OSV export", &outputs.osv_file, osv::write),
+        (
+            "gitlab",
+            "GitLab report",
+            &outputs.gitlab_file,
+            gitlab::write,
+        ),
+        ("html", "HTML report", &outputs.html_report, html::write),
+    ];
+    let mut reporters: Vec<Box<dyn Reporter + '_>> = vec![Box::new(SummaryReporter)];
+    for (name, description, path, write) in files {
+        if let Some(path) = path {
+            reporters.push(Box::new(FileReporter {
+                name,
+                description,
+                path,
+                write,
+            }));
+        }
+    }
+
+    // shared by the GitHub API and webhook requests
+    let client = client()?;
+    if config.create_issue || config.dry_run {
+        reporters.push(Box::new(IssueReporter {
+            client: client.clone(),
+            config,
+        }));
+    }
+    if let Some(email) = &config.email {
+        reporters.push(Box::new(EmailReporter {
+            email,
+            dry_run: config.dry_run,
+            repository: config.owner_repo.as_deref(),
+        }));
+    }
+    if let Some(webhook) = &config.webhook {
+        reporters.push(Box::new(WebhookReporter {
+            client,
+            webhook,
+            dry_run: config.dry_run,
+            repository: config.owner_repo.as_deref(),
+        }));
+    }
+    Ok(reporters)
+}
+
+/// Run every reporter, a failing reporter never keeps the others from
+/// running. Failures are logged, except for the first one of a reporter that
+/// fails the check, which is returned.
+pub(crate) async fn run(reporters: &[Box<dyn Reporter + '_>], report: &Report<'_>) -> Result<()> {
+    let mut failure = None;
+    for reporter in reporters {
+        match reporter.report(report).await {
+            Ok(()) => {}
+            Err(e) if reporter.fails_check() && failure.is_none() => failure = Some(e),
+            Err(e) => error!("The {} reporter failed: {e:#}", reporter.name()),
+        }
+    }
+    failure.map_or(Ok(()), Err)
+}
+
+type Write = fn(&str, &Report<'_>) -> Result<()>;
+
+/// Appends the findings to the GitHub Actions job summary.
+#[derive(Clone, Copy, Debug)]
+struct SummaryReporter;
+
+impl Reporter for SummaryReporter {
+    fn name(&self) -> &'static str {
+        "summary"
+    }
+
+    fn report<'a>(&'a self, report: &'a Report<'a>) -> ReporterFuture<'a> {
+        Box::pin(async move {
+            summary::write(report.findings, report.diagnostics, report.toolchain)
+                .context("unable to write the job summary")
+        })
+    }
+}
+
+/// Writes one of the report formats to a file.
+#[derive(Clone, Copy, Debug)]
+struct FileReporter<'a> {
+    name: &'static str,
+    description: &'static str,
+    path: &'a str,
+    write: Write,
+}
+
+impl Reporter for FileReporter<'_> {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn report<'a>(&'a self, report: &'a Report<'a>) -> ReporterFuture<'a> {
+        Box::pin(async move {
+            (self.write)(self.path, report)
+                .with_context(|| format!("unable to write '{}'", self.path))?;
+            info!("{} written to '{}'", self.description, self.path);
+            Ok(())
+        })
+    }
+}
+
+/// Emails a digest of the advisories when the check fails, like the issue.
+#[derive(Clone, Debug)]
+struct EmailReporter<'a> {
+    email: &'a Email,
+    dry_run: bool,
+    repository: Option<&'a str>,
+}
+
+impl Reporter for EmailReporter<'_> {
+    fn name(&self) -> &'static str {
+        "email"
+    }
+
+    fn report<'a>(&'a self, report: &'a Report<'a>) -> ReporterFuture<'a> {
+        Box::pin(async move {
+            if report.status() != Status::Failed || self.dry_run {
+                return Ok(());
+            }
+            let message = email::message(
+                self.email,
+                self.repository,
+                &report.advisories(),
+                report.toolchain,
+            )?;
+            email::send(self.email, message).await?;
+            info!(
+                "Advisory digest emailed to {} recipient(s)",
+                self.email.to.len()
+            );
+            Ok(())
+        })
+    }
+}
+
+/// Posts the advisory changes to a webhook, unless `cargo audit` failed, or
+/// only logs them on a dry run.
+#[derive(Clone, Debug)]
+struct WebhookReporter<'a> {
+    client: Client,
+    webhook: &'a Webhook,
+    dry_run: bool,
+    repository: Option<&'a str>,
+}
+
+impl Reporter for WebhookReporter<'_> {
+    fn name(&self) -> &'static str {
+        "webhook"
+    }
+
+    fn report<'a>(&'a self, report: &'a Report<'a>) -> ReporterFuture<'a> {
+        Box::pin(async move {
+            if report.status() == Status::Error {
+                return Ok(());
+            }
+            let posted = notify_changes(
+                &self.client,
+                &self.webhook.url,
+                self.webhook.format,
+                &self.webhook.state_file,
+                self.repository,
+                report.findings,
+                self.dry_run,
+            )
+            .await?;
+            if posted {
+                info!("Advisory changes posted to the webhook");
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Reporter, ReporterFuture, run, select};
+    use crate::{
+        config::{Config, test::config},
+        error::AuditCheckError,
+        report::Report,
+        toolchain::test::toolchain,
+    };
+    use anyhow::{Result, anyhow};
+    use std::cell::Cell;
+    use time::OffsetDateTime;
+
+    struct Counting<'a> {
+        calls: &'a Cell<usize>,
+        fail: bool,
+        fails_check: bool,
+    }
+
+    impl Reporter for Counting<'_> {
+        fn name(&self) -> &'static str {
+            "counting"
+        }
+
+        fn report<'a>(&'a self, _report: &'a Report<'a>) -> ReporterFuture<'a> {
+            Box::pin(async move {
+                self.calls.set(self.calls.get() + 1);
+                if self.fail {
+                    Err(anyhow!("boom"))
+                } else {
+                    Ok(())
+                }
+            })
+        }
+
+        fn fails_check(&self) -> bool {
+            self.fails_check
+        }
+    }
+
+    fn names(config: &Config) -> Result<Vec<&'static str>> {
+        Ok(select(config)?
+            .iter()
+            .map(|reporter| reporter.name())
+            .collect())
+    }
+
+    #[test]
+    fn select_works() -> Result<()> {
+        let mut config = config();
+        // the default test config is a dry run
+        assert_eq!(vec!["summary", "issue"], names(&config)?);
+        config.dry_run = false;
+        config.outputs.junit_file = Some("audit-check.xml".to_string());
+        config.outputs.html_report = Some("audit-check.html".to_string());
+        assert_eq!(vec!["summary", "junit", "html"], names(&config)?);
+        Ok(())
+    }
+
+    fn counting(calls: &Cell<usize>, fail: bool, fails_check: bool) -> Box<dyn Reporter + '_> {
+        Box::new(Counting {
+            calls,
+            fail,
+            fails_check,
+        })
+    }
+
+    #[tokio::test]
+    async fn failing_reporters_do_not_stop_the_others() {
+        let calls = Cell::new(0);
+        let toolchain = toolchain();
+        let result = Err(AuditCheckError::RustSec.into());
+        let report = Report {
+            findings: &[],
+            diagnostics: &[],
+            toolchain: &toolchain,
+            lockfile: None,
+            started: OffsetDateTime::UNIX_EPOCH,
+            result: &result,
+        };
+
+        let reporters = vec![
+            counting(&calls, true, false),
+            counting(&calls, false, false),
+        ];
+        assert!(run(&reporters, &report).await.is_ok());
+        assert_eq!(2, calls.get());
+
+        // only failures of reporters that fail the check are returned
+        let reporters = vec![counting(&calls, true, true), counting(&calls, false, false)];
+        assert!(run(&reporters, &report).await.is_err());
+        assert_eq!(4, calls.get());
+    }
+}
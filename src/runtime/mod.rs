@@ -7,82 +7,49 @@
 // modified, or distributed except according to those terms.
 
 use crate::{
-    audit::audit,
+    advisory::{Rustsec, add_dependency_paths, parse, parse_database},
+    audit::{AuditOutput, audit},
     check::{
-        installed::check_audit,
+        install::ensure_audit,
         rustc::{MSRV, check_rustc_version},
     },
+    cli::{Cli, Format},
     config::Config,
     error::AuditCheckError,
+    lockfile::Lockfile,
     log::initialize,
-    utils::handle_join_error,
+    policy::{Finding, Policies, Policy, evaluate, outcome},
+    report::Report,
+    reporter,
+    toolchain::Toolchain,
 };
 use anyhow::Result;
-use regex::Regex;
-use reqwest::{
-    Client, Version,
-    header::{HeaderMap, HeaderValue},
-};
 use rustc_version::version_meta;
-use serde::{Deserialize, Serialize};
-use std::{
-    collections::BTreeMap,
-    sync::{
-        LazyLock,
-        mpsc::{Receiver, channel},
-    },
-    thread::spawn,
-};
+use serde::Serialize;
+use time::OffsetDateTime;
 use tokio::runtime::Runtime;
-use tracing::{error, info, trace};
+use tracing::{info, warn};
 
-pub(crate) fn run() -> Result<()> {
-    let config = Config::from_env()?;
-    initialize(config.level)?;
-    if check_rustc_version(&version_meta()?)? {
-        trace!("rustc version check successful");
-        match check_audit("cargo audit --version") {
-            Ok(success) => {
-                if success {
-                    trace!("cargo audit version check successful");
-
-                    // channels for thread comms
-                    let (tx_stdout, rx_stdout) = channel();
-                    let (tx_stderr, rx_stderr) = channel();
-                    let (tx_code, rx_code) = channel();
+const AUDIT_VERSION: &str = "cargo audit --version";
+const LOCKFILE: &str = "Cargo.lock";
 
-                    // start the threads
-                    let deny_c = config.deny.clone();
-                    let audit_handle = spawn(move || audit(&deny_c, tx_stdout, tx_stderr, tx_code));
-                    let stdout_handle = spawn(move || receive_stdout(&rx_stdout));
-                    let stderr_handle = spawn(move || receive_stderr(&rx_stderr));
-                    let code_handle = spawn(move || receive_code(&rx_code));
+pub(crate) fn run(cli: Cli) -> Result<()> {
+    let config = Config::from_cli(cli)?;
+    initialize(config.level)?;
+    Runtime::new()?.block_on(check(config))
+}
 
-                    // wait for the thread to finish
-                    audit_handle.join().map_err(handle_join_error)??;
-                    let stdout_buf = stdout_handle.join().map_err(handle_join_error)?;
-                    let stderr_buf = stderr_handle.join().map_err(handle_join_error)?;
-                    let code = code_handle.join().map_err(handle_join_error)?;
-                    if code == 0 {
-                        Ok(())
-                    } else if config.create_issue {
-                        // Create the runtime
-                        let rt = Runtime::new()?;
-                        rt.block_on(async move {
-                            match create_issue(config, stdout_buf, stderr_buf).await {
-                                Ok(resp) => {
-                                    info!("Issue {} created", resp.id);
-                                }
-                                Err(e) => error!("{e}"),
-                            }
-                        });
-                        Err(AuditCheckError::RustSec.into())
-                    } else {
-                        Err(AuditCheckError::RustSec.into())
-                    }
-                } else {
-                    Err(AuditCheckError::AuditVersionCheck.into())
-                }
+async fn check(config: Config) -> Result<()> {
+    let started = OffsetDateTime::now_utc();
+    let rustc = version_meta()?;
+    if check_rustc_version(&rustc)? {
+        info!("rustc {} ({})", rustc.semver, rustc.host);
+        match ensure_audit(&config.install, AUDIT_VERSION, config.timeout).await {
+            Ok(version) => {
+                info!("cargo audit {version}");
+                let toolchain = Toolchain::new(&rustc, &version);
+                let output = audit(&config.deny, config.timeout).await?;
+                check_output(&config, toolchain, &output, started).await
             }
             Err(e) => Err(e.context("cargo audit check has failed")),
         }
@@ -91,252 +58,234 @@ pub(crate) fn run() -> Result<()> {
     }
 }
 
-fn receive_stdout(rx: &Receiver<String>) -> Vec<String> {
-    let mut buf = vec![];
-    while let Ok(message) = rx.recv() {
-        info!("{message}");
-        buf.push(message);
+// Evaluate what cargo audit reported and run the reporters on it.
+async fn check_output(
+    config: &Config,
+    mut toolchain: Toolchain,
+    output: &AuditOutput,
+    started: OffsetDateTime,
+) -> Result<()> {
+    let stdout = output.stdout.join("\n");
+    toolchain.advisory_db = parse_database(&stdout);
+    if let Some(database) = &toolchain.advisory_db {
+        info!(
+            "advisory-db {} ({} advisories)",
+            database.last_commit.as_deref().unwrap_or("unknown commit"),
+            database.advisory_count
+        );
     }
-    buf
-}
-
-fn receive_stderr(rx: &Receiver<String>) -> Vec<String> {
-    let mut buf = vec![];
-    while let Ok(message) = rx.recv() {
-        info!("{message}");
-        buf.push(message);
+    let lockfile = load_lockfile();
+    let reporters = reporter::select(config)?;
+    let parsed = advisories(&stdout, lockfile.as_ref()).and_then(|rustsecs| {
+        check_exit_code(output, &rustsecs, &config.deny)?;
+        Ok(rustsecs)
+    });
+    let rustsecs = match parsed {
+        Ok(rustsecs) => rustsecs,
+        Err(e) => {
+            let result = Err(tool_error(output).unwrap_or(e));
+            let reported = reporter::run(
+                &reporters,
+                &Report {
+                    findings: &[],
+                    diagnostics: &output.diagnostics,
+                    toolchain: &toolchain,
+                    lockfile: lockfile.as_ref(),
+                    started,
+                    result: &result,
+                },
+            )
+            .await;
+            return reported.and(result);
+        }
+    };
+    let findings = evaluate(config, rustsecs);
+    log_findings(&findings);
+    // only failing findings fail from here on, so an issue always has
+    // advisories in it
+    let result = outcome(&findings);
+    let report = Report {
+        findings: &findings,
+        diagnostics: &output.diagnostics,
+        toolchain: &toolchain,
+        lockfile: lockfile.as_ref(),
+        started,
+        result: &result,
+    };
+    if config.format == Format::Json {
+        let json = JsonOutput {
+            toolchain: &toolchain,
+            advisories: &report.advisories(),
+        };
+        println!("{}", serde_json::to_string_pretty(&json)?);
     }
-    buf
-}
-
-fn receive_code(rx: &Receiver<i32>) -> i32 {
-    rx.recv().unwrap_or(-1)
+    let reported = reporter::run(&reporters, &report).await;
+    if result.is_ok() && output.code != 0 {
+        info!("No advisories fail the configured policies");
+    }
+    // a failed issue takes precedence, the advisories are in the log and the
+    // job summary either way
+    reported.and(result)
 }
 
+// The `--format json` output
 #[derive(Clone, Debug, Serialize)]
-struct Issue {
-    title: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    body: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    milestone: Option<usize>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    labels: Option<Vec<String>>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    assignees: Option<Vec<String>>,
+struct JsonOutput<'a> {
+    toolchain: &'a Toolchain,
+    advisories: &'a [Rustsec],
 }
 
-#[derive(Clone, Debug, Deserialize)]
-struct Resp {
-    id: usize,
+fn load_lockfile() -> Option<Lockfile> {
+    Lockfile::load(LOCKFILE)
+        .map_err(|e| warn!("Unable to read '{LOCKFILE}', dependency paths are unavailable: {e}"))
+        .ok()
 }
 
-static APP_USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"),);
-
-static CRATE_REGEX: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"Crate: +(.*)").expect("Invalid CRATE_REGEX"));
-static VERSION_REGEX: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"Version: +(.*)").expect("Invalid VERSION_REGEX"));
-static WARNING_REGEX: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"Warning: +(.*)").expect("Invalid WARNING_REGEX"));
-static TITLE_REGEX: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"Title: +(.*)").expect("Invalid TITLE_REGEX"));
-static DATE_REGEX: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"Date: +(.*)").expect("Invalid DATE_REGEX"));
-static SOLUTION_REGEX: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"Solution: +(.*)").expect("Invalid SOLUTION_REGEX"));
-static ID_REGEX: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"ID: +(RUSTSEC.*)").expect("Invalid ID_REGEX"));
-static URL_REGEX: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"URL: +(https:.*)").expect("Invalid URL_REGEX"));
-
-#[allow(dead_code)]
-#[derive(Clone, Debug)]
-struct Rustsec {
-    id: String,
-    url: String,
-    krate: String,
-    version: String,
-    warning: String,
-    title: String,
-    date: String,
-    solution: String,
+fn advisories(stdout: &str, lockfile: Option<&Lockfile>) -> Result<Vec<Rustsec>> {
+    let mut rustsecs = parse(stdout)?;
+    if let Some(lockfile) = lockfile {
+        add_dependency_paths(&mut rustsecs, lockfile);
+    }
+    Ok(rustsecs)
 }
 
-async fn create_issue(
-    config: Config,
-    stdout_buf: Vec<String>,
-    _stderr_buf: Vec<String>,
-) -> Result<Resp> {
-    let token = config.token;
-    let owner_repo = config.owner_repo;
-    let stdout = stdout_buf.join("\n");
-    let rustsec_map = parse(&stdout);
-    let title = generate_title(&rustsec_map);
-    let body = generate_body(&rustsec_map);
-
-    let mut headers = HeaderMap::new();
-    let _old = headers.insert(
-        "Accept",
-        HeaderValue::from_static("application/vnd.github+json"),
-    );
-    let _old = headers.insert(
-        "X-GitHub-Api-Version",
-        HeaderValue::from_static("2022-11-28"),
-    );
-    let client = Client::builder()
-        .user_agent(APP_USER_AGENT)
-        .default_headers(headers)
-        .build()?;
-
-    let url = format!("https://api.github.com/repos/{owner_repo}/issues");
-    let issue = Issue {
-        title,
-        body: Some(body),
-        milestone: None,
-        labels: None,
-        assignees: None,
-    };
-    let res = client
-        .post(&url)
-        .version(Version::HTTP_11)
-        .bearer_auth(token)
-        .json(&issue)
-        .send()
-        .await?;
-
-    if res.status() == 201 {
-        Ok(res.json::<Resp>().await?)
+// cargo audit exits with a non-zero code when it finds advisories denied by
+// `-D<deny>`, any other failure is the tool failing rather than a finding.
+fn check_exit_code(output: &AuditOutput, rustsecs: &[Rustsec], deny: &str) -> Result<()> {
+    let denied = Policies::from_deny(deny);
+    if output.code == 0
+        || rustsecs
+            .iter()
+            .any(|rustsec| denied.get(rustsec.kind) == Policy::Fail)
+    {
+        Ok(())
     } else {
-        let body = res.bytes().await?;
-        error!("{}", String::from_utf8_lossy(&body));
-        Err(AuditCheckError::CreateIssue.into())
+        Err(AuditCheckError::AuditExit { code: output.code }.into())
     }
 }
 
-fn parse(output: &str) -> BTreeMap<String, (String, Rustsec)> {
-    let mut splits: Vec<String> = output.split("\n\n").map(str::to_string).collect();
-
-    splits[0] = splits[0].lines().skip(4).collect::<Vec<&str>>().join("\n");
-
-    splits
+// The first error cargo audit reported, a failed run must not be mistaken for
+// advisories being found.
+fn tool_error(output: &AuditOutput) -> Option<anyhow::Error> {
+    output
+        .diagnostics
         .iter()
-        .map(|x| (x, parse_rustsec(x)))
-        .map(|(s, x)| (x.id.clone(), ((*s).to_string(), x)))
-        .collect()
+        .find(|diagnostic| diagnostic.is_error())
+        .map(|diagnostic| {
+            AuditCheckError::AuditFailed {
+                message: diagnostic.message.clone(),
+            }
+            .into()
+        })
 }
 
-fn parse_rustsec(rustsec_str: &str) -> Rustsec {
-    let id = parse_caps(&ID_REGEX, rustsec_str, "No ID");
-    let url = parse_caps(&URL_REGEX, rustsec_str, "No URL");
-    let krate = parse_caps(&CRATE_REGEX, rustsec_str, "No Crate");
-    let version = parse_caps(&VERSION_REGEX, rustsec_str, "No Version");
-    let warning = parse_caps(&WARNING_REGEX, rustsec_str, "No Warning");
-    let title = parse_caps(&TITLE_REGEX, rustsec_str, "No Title");
-    let date = parse_caps(&DATE_REGEX, rustsec_str, "No Date");
-    let solution = parse_caps(&SOLUTION_REGEX, rustsec_str, "No Solution");
-
-    Rustsec {
-        id,
-        url,
-        krate,
-        version,
-        warning,
-        title,
-        date,
-        solution,
+fn log_findings(findings: &[Finding]) {
+    for Finding { rustsec, decision } in findings {
+        info!(
+            "{} ({}, {} priority): {} {} - {}",
+            rustsec.id,
+            rustsec.kind,
+            rustsec.kind.priority().as_str(),
+            rustsec.krate,
+            rustsec.version,
+            rustsec.title
+        );
+        info!("{} is {}: {}", rustsec.id, decision.policy, decision.reason);
     }
 }
 
-fn parse_caps(regex: &Regex, rustsec_str: &str, default: &str) -> String {
-    regex
-        .captures(rustsec_str)
-        .map_or_else(
-            || default,
-            |caps| caps.get(1).map_or(default, |m| m.as_str()),
-        )
-        .to_string()
-}
-
-fn generate_title(rustsec_map: &BTreeMap<String, (String, Rustsec)>) -> String {
-    rustsec_map.keys().fold(String::new(), |acc, key| {
-        if acc.is_empty() {
-            acc + key
-        } else {
-            acc + ", " + key
-        }
-    })
-}
-
-fn generate_body(rustsec_map: &BTreeMap<String, (String, Rustsec)>) -> String {
-    rustsec_map.iter().fold(String::new(), |acc, (k, v)| {
-        acc + &format!("# ‼️ {} ‼️\n{}\n\n````\n{}\n````\n\n", k, v.1.url, v.0)
-    })
-}
-
 #[cfg(test)]
 mod test {
-    use super::{generate_title, parse};
-
-    const TEST_RUSTSEC: &str = r"Crate:     aovec
-Version:   1.1.0
-Title:     Aovec<T> lacks bound on its Send and Sync traits allowing data races
-Date:      2020-12-10
-ID:        RUSTSEC-2020-0099
-URL:       https://rustsec.org/advisories/RUSTSEC-2020-0099
-Solution:  No fixed upgrade is available!
-Dependency tree:
-aovec 1.1.0
-└── audit-check-test 0.1.0
-
-Crate:     owning_ref
-Version:   0.3.3
-Title:     Multiple soundness issues in `owning_ref`
-Date:      2022-01-26
-ID:        RUSTSEC-2022-0040
-URL:       https://rustsec.org/advisories/RUSTSEC-2022-0040
-Solution:  No fixed upgrade is available!
-Dependency tree:
-owning_ref 0.3.3
-└── parking_lot 0.4.8
-    └── aovec 1.1.0
-        └── audit-check-test 0.1.0
-
-Crate:     anymap
-Version:   0.12.1
-Warning:   unmaintained
-Title:     anymap is unmaintained.
-Date:      2021-05-07
-ID:        RUSTSEC-2021-0065
-URL:       https://rustsec.org/advisories/RUSTSEC-2021-0065
-Dependency tree:
-anymap 0.12.1
-└── audit-check-test 0.1.0
+    use super::{check_exit_code, check_output, tool_error};
+    use crate::{
+        advisory::{AdvisoryKind, parse, test::TEST_REPORT},
+        audit::AuditOutput,
+        config::test::config,
+        diagnostic::Diagnostic,
+        error::{AuditCheckError, EXIT_NOTIFIER, exit_code},
+        toolchain::test::toolchain,
+    };
+    use anyhow::Result;
+    use time::OffsetDateTime;
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{method, path},
+    };
 
-Crate:     smallvec
-Version:   0.4.5
-Warning:   unsound
-Title:     smallvec creates uninitialized value of any type
-Date:      2018-09-25
-ID:        RUSTSEC-2018-0018
-URL:       https://rustsec.org/advisories/RUSTSEC-2018-0018
-Dependency tree:
-smallvec 0.4.5
-└── aovec 1.1.0
-    └── audit-check-test 0.1.0
-";
+    fn audit_output(code: i32, stderr: &[&str]) -> AuditOutput {
+        AuditOutput {
+            stdout: vec![],
+            diagnostics: stderr
+                .iter()
+                .map(|line| Diagnostic::classify(line))
+                .collect(),
+            code,
+        }
+    }
 
     #[test]
-    fn parse_works() {
-        assert_eq!(4, parse(TEST_RUSTSEC).len());
+    fn check_exit_code_works() -> Result<()> {
+        let rustsecs = parse(TEST_REPORT)?;
+        let unmaintained: Vec<_> = rustsecs
+            .iter()
+            .filter(|rustsec| rustsec.kind == AdvisoryKind::Unmaintained)
+            .cloned()
+            .collect();
+        assert!(check_exit_code(&audit_output(0, &[]), &[], "warnings").is_ok());
+        assert!(check_exit_code(&audit_output(1, &[]), &rustsecs, "unsound").is_ok());
+        assert!(check_exit_code(&audit_output(1, &[]), &unmaintained, "warnings").is_ok());
+        // failed without a denied advisory
+        for (rustsecs, deny) in [(&[][..], "warnings"), (&unmaintained[..], "unsound")] {
+            assert!(matches!(
+                check_exit_code(&audit_output(1, &[]), rustsecs, deny)
+                    .err()
+                    .map(anyhow::Error::downcast::<AuditCheckError>),
+                Some(Ok(AuditCheckError::AuditExit { code: 1 }))
+            ));
+        }
+        Ok(())
     }
 
     #[test]
-    fn generate_title_works() {
-        let rustsec_map = parse(TEST_RUSTSEC);
+    fn tool_error_works() {
+        let output = audit_output(
+            1,
+            &[
+                "    Fetching advisory database from `https://github.com/RustSec/advisory-db.git`",
+                "error: couldn't fetch advisory database: git operation failed",
+            ],
+        );
         assert_eq!(
-            "RUSTSEC-2018-0018, RUSTSEC-2020-0099, RUSTSEC-2021-0065, RUSTSEC-2022-0040",
-            generate_title(&rustsec_map)
+            Some(
+                "cargo audit failed: error: couldn't fetch advisory database: git operation failed"
+                    .to_string()
+            ),
+            tool_error(&output).map(|e| e.to_string())
         );
+        assert!(tool_error(&audit_output(1, &["warning: 1 allowed warning found"])).is_none());
+    }
+
+    #[tokio::test]
+    async fn failed_issue_exits_with_the_github_code() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/repos/rustyhorde/audit-check/issues"))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let mut config = config();
+        config.token = Some("abc".to_string());
+        config.create_issue = true;
+        config.dry_run = false;
+        config.api_url = server.uri();
+        let output = AuditOutput {
+            stdout: TEST_REPORT.lines().map(str::to_string).collect(),
+            diagnostics: vec![],
+            code: 1,
+        };
+
+        let result = check_output(&config, toolchain(), &output, OffsetDateTime::UNIX_EPOCH).await;
+        assert_eq!(Some(EXIT_NOTIFIER), result.err().map(|e| exit_code(&e)));
     }
 }
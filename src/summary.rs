@@ -0,0 +1,149 @@
+// Copyright (c) 2023 audit-check developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or https://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use crate::{diagnostic::Diagnostic, policy::Finding, toolchain::Toolchain};
+use anyhow::Result;
+use std::{env, fmt::Write as _, fs::OpenOptions, io::Write};
+
+/// The file GitHub Actions renders as the job summary.
+const GITHUB_STEP_SUMMARY: &str = "GITHUB_STEP_SUMMARY";
+
+/// Append the findings to the job summary, when running as a GitHub Action.
+pub(crate) fn write(
+    findings: &[Finding],
+    diagnostics: &[Diagnostic],
+    toolchain: &Toolchain,
+) -> Result<()> {
+    if let Some(path) = env::var_os(GITHUB_STEP_SUMMARY).filter(|path| !path.is_empty()) {
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        file.write_all(render(findings, diagnostics, toolchain).as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Render the findings as Markdown, highest priority first, preceded by any
+/// errors `cargo audit` reported and followed by the toolchain.
+pub(crate) fn render(
+    findings: &[Finding],
+    diagnostics: &[Diagnostic],
+    toolchain: &Toolchain,
+) -> String {
+    let mut summary = "## cargo audit\n\n".to_string();
+    let errors: Vec<&Diagnostic> = diagnostics.iter().filter(|d| d.is_error()).collect();
+    if !errors.is_empty() {
+        summary.push_str("### Errors\n\n");
+        for diagnostic in &errors {
+            let _ = writeln!(summary, "- {}: {}", diagnostic.kind, diagnostic.message);
+        }
+        summary.push('\n');
+    }
+    if findings.is_empty() {
+        if errors.is_empty() {
+            summary.push_str("No advisories found.\n\n");
+        }
+    } else {
+        findings_table(&mut summary, findings);
+    }
+    summary.push_str("### Toolchain\n\n");
+    summary.push_str(&toolchain.markdown());
+    summary
+}
+
+fn findings_table(summary: &mut String, findings: &[Finding]) {
+    summary.push_str("| Priority | Advisory | Kind | Crate | Policy |\n");
+    summary.push_str("|----------|----------|------|-------|--------|\n");
+    for Finding { rustsec, decision } in findings {
+        let _ = writeln!(
+            summary,
+            "| {} | [{}]({}) | {} | {} {} | {} ({}) |",
+            rustsec.kind.priority().as_str(),
+            cell(&rustsec.id),
+            cell(&rustsec.url),
+            rustsec.kind,
+            cell(&rustsec.krate),
+            cell(&rustsec.version),
+            decision.policy,
+            cell(&decision.reason),
+        );
+    }
+    summary.push('\n');
+}
+
+// Ignore reasons are free-form, and a `|` or a line break would end the
+// table cell or row.
+fn cell(text: &str) -> String {
+    text.replace('|', "\\|")
+        .replace("\r\n", " ")
+        .replace(['\r', '\n'], " ")
+}
+
+#[cfg(test)]
+mod test {
+    use super::render;
+    use crate::{
+        advisory::{parse, test::TEST_REPORT},
+        config::{Ignore, test::config},
+        diagnostic::Diagnostic,
+        policy::evaluate,
+        toolchain::test::toolchain,
+    };
+    use anyhow::Result;
+
+    #[test]
+    fn render_works() -> Result<()> {
+        let summary = render(&evaluate(&config(), parse(TEST_REPORT)?), &[], &toolchain());
+        let rows: Vec<&str> = summary.lines().skip(4).take(4).collect();
+        assert!(summary.contains("\n\n### Toolchain\n\n| Tool | Version |\n"));
+        assert_eq!(
+            "| high | [RUSTSEC-2020-0099](https://rustsec.org/advisories/RUSTSEC-2020-0099) | vulnerability | aovec 1.1.0 | fail (vulnerability_policy is fail) |",
+            rows[0]
+        );
+        assert!(rows[3].starts_with("| low | [RUSTSEC-2021-0065]"));
+        Ok(())
+    }
+
+    #[test]
+    fn render_escapes_cells() -> Result<()> {
+        let mut config = config();
+        config.ignore.push(Ignore::new(
+            "RUSTSEC-2020-0099",
+            "only used in tests | benches\nuntil 2.0",
+            "deny.toml",
+        ));
+        let summary = render(&evaluate(&config, parse(TEST_REPORT)?), &[], &toolchain());
+        let aovec = summary
+            .lines()
+            .find(|line| line.contains("RUSTSEC-2020-0099"))
+            .unwrap_or_default();
+        assert!(aovec.ends_with(
+            "| ignore (ignored by 'deny.toml': only used in tests \\| benches until 2.0) |"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn render_without_findings_works() {
+        assert!(
+            render(&[], &[], &toolchain())
+                .starts_with("## cargo audit\n\nNo advisories found.\n\n### Toolchain\n")
+        );
+    }
+
+    #[test]
+    fn render_includes_errors() {
+        let diagnostics = [
+            Diagnostic::classify(
+                "    Fetching advisory database from `https://github.com/RustSec/advisory-db.git`",
+            ),
+            Diagnostic::classify("error: couldn't fetch advisory database: git operation failed"),
+        ];
+        assert!(render(&[], &diagnostics, &toolchain()).starts_with(
+            "## cargo audit\n\n### Errors\n\n- advisory database error: error: couldn't fetch advisory database: git operation failed\n\n### Toolchain\n"
+        ));
+    }
+}
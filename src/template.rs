@@ -0,0 +1,156 @@
+// Copyright (c) 2023 audit-check developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or https://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use crate::{
+    advisory::{Priority, Rustsec},
+    error::AuditCheckError,
+    toolchain::Toolchain,
+};
+use anyhow::Result;
+use minijinja::{Environment, context};
+use serde::Serialize;
+
+/// An advisory as seen by templates, with its priority.
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct AdvisoryContext<'a> {
+    #[serde(flatten)]
+    rustsec: &'a Rustsec,
+    priority: Priority,
+}
+
+/// Render a user supplied issue template.
+///
+/// The template has access to `advisories`, a list with the `id`, `crate`,
+/// `version`, `title`, `date`, `url`, `solution`, `kind`, `priority` and
+/// `dependency_paths` of every advisory that was found, and to `toolchain`
+/// with the `rustc`, `host`, `cargo_audit` and `advisory_db` the check ran with.
+pub(crate) fn render(
+    name: &'static str,
+    template: &str,
+    rustsecs: &[Rustsec],
+    toolchain: &Toolchain,
+) -> Result<String> {
+    let advisories = advisory_context(rustsecs);
+
+    let mut env = Environment::new();
+    env.add_template(name, template)
+        .map_err(|e| invalid(name, &e))?;
+    env.get_template(name)?
+        .render(context! { advisories, toolchain })
+        .map_err(|e| invalid(name, &e).into())
+}
+
+/// Check that a user supplied issue template compiles, so a bad template
+/// fails before `cargo audit` runs.
+pub(crate) fn validate(name: &'static str, template: &str) -> Result<()> {
+    drop(
+        Environment::new()
+            .template_from_str(template)
+            .map_err(|e| invalid(name, &e))?,
+    );
+    Ok(())
+}
+
+fn invalid(name: &'static str, e: &minijinja::Error) -> AuditCheckError {
+    AuditCheckError::InvalidInput {
+        input: name,
+        reason: e.to_string(),
+    }
+}
+
+/// An environment with the templates bundled with audit-check, keyed by name.
+///
+/// Escaping follows the name, templates ending in `.html` are HTML escaped.
+pub(crate) fn bundled(templates: &[(&'static str, &'static str)]) -> Result<Environment<'static>> {
+    let mut env = Environment::new();
+    env.set_trim_blocks(true);
+    env.set_lstrip_blocks(true);
+    for (name, source) in templates {
+        env.add_template(name, source)?;
+    }
+    Ok(env)
+}
+
+/// The advisories in the form templates see them.
+pub(crate) fn advisory_context(rustsecs: &[Rustsec]) -> Vec<AdvisoryContext<'_>> {
+    rustsecs
+        .iter()
+        .map(|rustsec| AdvisoryContext {
+            rustsec,
+            priority: rustsec.kind.priority(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{render, validate};
+    use crate::{
+        advisory::{parse, test::TEST_REPORT},
+        toolchain::test::toolchain,
+    };
+    use anyhow::Result;
+
+    #[test]
+    fn render_works() -> Result<()> {
+        let rustsecs = parse(TEST_REPORT)?;
+        let title = render(
+            "issue_title_template",
+            "{{ advisories | length }} advisories in {{ advisories | map(attribute='crate') | join(', ') }}",
+            &rustsecs,
+            &toolchain(),
+        )?;
+        assert_eq!("4 advisories in smallvec, aovec, anymap, owning_ref", title);
+        Ok(())
+    }
+
+    #[test]
+    fn render_exposes_kind() -> Result<()> {
+        let rustsecs = parse(TEST_REPORT)?;
+        let body = render(
+            "issue_body_template",
+            "{% for a in advisories %}{{ a.id }}={{ a.kind }}/{{ a.priority }};{% endfor %}",
+            &rustsecs,
+            &toolchain(),
+        )?;
+        assert_eq!(
+            "RUSTSEC-2018-0018=unsound/medium;RUSTSEC-2020-0099=vulnerability/high;RUSTSEC-2021-0065=unmaintained/low;RUSTSEC-2022-0040=vulnerability/high;",
+            body
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn render_exposes_toolchain() -> Result<()> {
+        let rustsecs = parse(TEST_REPORT)?;
+        let footer = render(
+            "issue_body_template",
+            "cargo audit {{ toolchain.cargo_audit }}, advisory-db {{ toolchain.advisory_db.last_commit }}",
+            &rustsecs,
+            &toolchain(),
+        )?;
+        assert_eq!(
+            "cargo audit 0.21.2, advisory-db a9f5f1d5d5ba4d7f9e8b0f9d2e4a0c1b2d3e4f5a",
+            footer
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn render_reports_bad_templates() -> Result<()> {
+        let rustsecs = parse(TEST_REPORT)?;
+        assert!(render("issue_title_template", "{% for %}", &rustsecs, &toolchain()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn validate_works() {
+        assert!(validate("issue_title_template", "{{ advisories | length }}").is_ok());
+        assert!(validate("issue_title_template", "{% for %}").is_err());
+    }
+}
@@ -0,0 +1,116 @@
+// Copyright (c) 2023 audit-check developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or https://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use crate::advisory::Database;
+use rustc_version::{Version, VersionMeta};
+use serde::Serialize;
+use std::fmt::Write as _;
+
+const ADVISORY_DB_COMMIT_URL: &str = "https://github.com/RustSec/advisory-db/commit";
+
+/// The tools and advisory database a check ran with, so findings can be
+/// reproduced against the same database state.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[non_exhaustive]
+pub struct Toolchain {
+    /// The rustc version, i.e. `1.85.0`
+    pub rustc: String,
+    /// The host triple, i.e. `x86_64-unknown-linux-gnu`
+    pub host: String,
+    /// The cargo audit version, i.e. `0.21.2`
+    pub cargo_audit: String,
+    /// The advisory database, when `cargo audit` reported it
+    pub advisory_db: Option<Database>,
+}
+
+impl Toolchain {
+    /// A toolchain from its versions, without an advisory database.
+    #[must_use]
+    pub fn from_parts(rustc: &str, host: &str, cargo_audit: &str) -> Self {
+        Self {
+            rustc: rustc.to_string(),
+            host: host.to_string(),
+            cargo_audit: cargo_audit.to_string(),
+            advisory_db: None,
+        }
+    }
+
+    pub(crate) fn new(rustc: &VersionMeta, cargo_audit: &Version) -> Self {
+        Self {
+            rustc: rustc.semver.to_string(),
+            host: rustc.host.clone(),
+            cargo_audit: cargo_audit.to_string(),
+            advisory_db: None,
+        }
+    }
+
+    /// The toolchain as a Markdown table.
+    pub(crate) fn markdown(&self) -> String {
+        let mut markdown = "| Tool | Version |\n|------|---------|\n".to_string();
+        let _ = writeln!(markdown, "| rustc | {} ({}) |", self.rustc, self.host);
+        let _ = writeln!(markdown, "| cargo audit | {} |", self.cargo_audit);
+        if let Some(database) = &self.advisory_db {
+            let commit = database.last_commit.as_deref().map_or_else(
+                || "unknown commit".to_string(),
+                |commit| format!("[{}]({ADVISORY_DB_COMMIT_URL}/{commit})", short(commit)),
+            );
+            let _ = write!(
+                markdown,
+                "| advisory-db | {commit}, {} advisories",
+                database.advisory_count
+            );
+            if let Some(last_updated) = &database.last_updated {
+                let _ = write!(markdown, ", updated {last_updated}");
+            }
+            markdown.push_str(" |\n");
+        }
+        markdown
+    }
+}
+
+fn short(commit: &str) -> &str {
+    commit.get(..7).unwrap_or(commit)
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::Toolchain;
+    use crate::advisory::{parse_database, test::TEST_REPORT};
+
+    /// A toolchain with the advisory database from the test report.
+    pub(crate) fn toolchain() -> Toolchain {
+        Toolchain {
+            rustc: "1.85.0".to_string(),
+            host: "x86_64-unknown-linux-gnu".to_string(),
+            cargo_audit: "0.21.2".to_string(),
+            advisory_db: parse_database(TEST_REPORT),
+        }
+    }
+
+    #[test]
+    fn markdown_works() {
+        assert_eq!(
+            "| Tool | Version |
+|------|---------|
+| rustc | 1.85.0 (x86_64-unknown-linux-gnu) |
+| cargo audit | 0.21.2 |
+| advisory-db | [a9f5f1d](https://github.com/RustSec/advisory-db/commit/a9f5f1d5d5ba4d7f9e8b0f9d2e4a0c1b2d3e4f5a), 554 advisories, updated 2023-03-01T10:00:00Z |
+",
+            toolchain().markdown()
+        );
+    }
+
+    #[test]
+    fn markdown_without_database_works() {
+        let toolchain = Toolchain {
+            advisory_db: None,
+            ..toolchain()
+        };
+        assert!(!toolchain.markdown().contains("advisory-db"));
+    }
+}
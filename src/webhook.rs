@@ -0,0 +1,478 @@
+// Copyright (c) 2023 audit-check developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or https://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use crate::{
+    advisory::{Priority, Rustsec},
+    error::AuditCheckError,
+    github::retry,
+    policy::Finding,
+};
+use anyhow::Result;
+use clap::ValueEnum;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::{collections::BTreeSet, fs, io::ErrorKind, path::Path};
+use tracing::info;
+
+/// The most advisories listed in a single notification.
+const MAX_LISTED: usize = 20;
+
+/// The payload flavor posted to the webhook.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum WebhookFormat {
+    /// A generic JSON digest
+    #[default]
+    Json,
+    /// Slack blocks, for incoming webhooks
+    Slack,
+    /// A Microsoft Teams `MessageCard`
+    Teams,
+}
+
+/// The advisories reported by the last notification.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+struct State {
+    advisories: BTreeSet<String>,
+}
+
+/// What changed since the last notification.
+#[derive(Clone, Debug)]
+struct Digest<'a> {
+    new: Vec<&'a Rustsec>,
+    resolved: Vec<String>,
+    total: usize,
+}
+
+/// Post a digest of the advisories to the webhook when they changed since
+/// the last notification, returning whether one was sent.
+///
+/// The reported advisories are kept in the state file, which is only updated
+/// once the webhook accepted the digest, so a failed post is retried on the
+/// next run. On a dry run the digest is only logged.
+pub(crate) async fn notify_changes(
+    client: &Client,
+    url: &str,
+    format: WebhookFormat,
+    state_file: &str,
+    repository: Option<&str>,
+    findings: &[Finding],
+    dry_run: bool,
+) -> Result<bool> {
+    let previous = load_state(state_file)?;
+    let (digest, state) = digest(&previous, findings);
+    if state == previous {
+        info!("No advisories changed since the last notification");
+        return Ok(false);
+    }
+    let payload = payload(format, &digest, repository);
+    if dry_run {
+        info!("Dry run, the following would be posted to the webhook");
+        info!("{}", serde_json::to_string_pretty(&payload)?);
+        return Ok(false);
+    }
+    send(client, url, &payload).await?;
+    fs::write(state_file, serde_json::to_string_pretty(&state)?)?;
+    Ok(true)
+}
+
+async fn send(client: &Client, url: &str, payload: &Value) -> Result<()> {
+    let res = retry(client.post(url).json(payload), "webhook").await?;
+    let status = res.status();
+    if status.is_success() {
+        Ok(())
+    } else {
+        let message = res.text().await.unwrap_or_default();
+        Err(AuditCheckError::Webhook {
+            status: status.as_u16(),
+            message,
+        }
+        .into())
+    }
+}
+
+fn load_state(path: &str) -> Result<State> {
+    match fs::read_to_string(Path::new(path)) {
+        Ok(contents) => Ok(serde_json::from_str(&contents)?),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(State::default()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+// Ignored advisories are never notified about.
+fn digest<'a>(previous: &State, findings: &'a [Finding]) -> (Digest<'a>, State) {
+    let reported: Vec<&Rustsec> = findings
+        .iter()
+        .filter(|finding| !finding.is_ignored())
+        .map(|finding| &finding.rustsec)
+        .collect();
+    let state = State {
+        advisories: reported.iter().map(|rustsec| key(rustsec)).collect(),
+    };
+    let digest = Digest {
+        new: reported
+            .iter()
+            .copied()
+            .filter(|rustsec| !previous.advisories.contains(&key(rustsec)))
+            .collect(),
+        resolved: previous
+            .advisories
+            .difference(&state.advisories)
+            .cloned()
+            .collect(),
+        total: reported.len(),
+    };
+    (digest, state)
+}
+
+// i.e. `RUSTSEC-2020-0099 aovec 1.1.0`
+fn key(rustsec: &Rustsec) -> String {
+    format!("{} {} {}", rustsec.id, rustsec.krate, rustsec.version)
+}
+
+fn headline(digest: &Digest<'_>, repository: Option<&str>) -> String {
+    let new = match digest.new.len() {
+        1 => "1 new advisory".to_string(),
+        new => format!("{new} new advisories"),
+    };
+    let mut headline = match (digest.new.len(), digest.resolved.len()) {
+        (0, resolved) if digest.total == 0 => {
+            format!("cargo audit: all {resolved} advisories resolved")
+        }
+        (0, resolved) => format!("cargo audit: {resolved} advisories resolved"),
+        (_, 0) => format!("cargo audit: {new}"),
+        (_, resolved) => format!("cargo audit: {new}, {resolved} resolved"),
+    };
+    if let Some(repository) = repository {
+        headline.push_str(" in ");
+        headline.push_str(repository);
+    }
+    headline
+}
+
+fn more(digest: &Digest<'_>) -> Option<String> {
+    (digest.new.len() > MAX_LISTED).then(|| format!("…and {} more", digest.new.len() - MAX_LISTED))
+}
+
+fn payload(format: WebhookFormat, digest: &Digest<'_>, repository: Option<&str>) -> Value {
+    let headline = headline(digest, repository);
+    match format {
+        WebhookFormat::Json => json!({
+            "text": headline,
+            "repository": repository,
+            "total": digest.total,
+            "new": digest.new,
+            "resolved": digest.resolved,
+        }),
+        WebhookFormat::Slack => slack(&headline, digest),
+        WebhookFormat::Teams => teams(&headline, digest),
+    }
+}
+
+fn slack(headline: &str, digest: &Digest<'_>) -> Value {
+    let mut blocks = vec![json!({
+        "type": "header",
+        "text": { "type": "plain_text", "text": headline },
+    })];
+    blocks.extend(digest.new.iter().take(MAX_LISTED).map(|rustsec| {
+        json!({
+            "type": "section",
+            "text": {
+                "type": "mrkdwn",
+                "text": format!(
+                    "*<{}|{}>* `{} {}`\n{} ({}, {} priority)\n{}",
+                    // `|` ends the link target
+                    escape(&rustsec.url).replace('|', "%7C"),
+                    rustsec.id,
+                    escape(&rustsec.krate),
+                    rustsec.version,
+                    escape(&rustsec.title),
+                    rustsec.kind,
+                    rustsec.kind.priority().as_str(),
+                    escape(&rustsec.solution)
+                ),
+            },
+        })
+    }));
+    let mut context: Vec<String> = more(digest).into_iter().collect();
+    if !digest.resolved.is_empty() {
+        context.push(format!("Resolved: {}", escape(&digest.resolved.join(", "))));
+    }
+    context.push(format!("{} advisories reported in total", digest.total));
+    blocks.push(json!({
+        "type": "context",
+        "elements": [{ "type": "mrkdwn", "text": context.join("\n") }],
+    }));
+    json!({ "text": headline, "blocks": blocks })
+}
+
+// Slack reads `<...>` as a link or mention in mrkdwn, so these three are
+// escaped in any text taken from an advisory.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn teams(headline: &str, digest: &Digest<'_>) -> Value {
+    // colored by the highest priority, green when advisories were only resolved
+    let color = match digest
+        .new
+        .iter()
+        .map(|rustsec| rustsec.kind.priority())
+        .max()
+    {
+        Some(Priority::High) => "CF222E",
+        Some(Priority::Medium) => "BC4C00",
+        Some(Priority::Low) => "9A6700",
+        None => "1A7F37",
+    };
+    let mut sections = vec![];
+    if !digest.new.is_empty() {
+        let facts: Vec<Value> = digest
+            .new
+            .iter()
+            .take(MAX_LISTED)
+            .map(|rustsec| {
+                json!({
+                    "name": format!("{} {}", rustsec.krate, rustsec.version),
+                    "value": format!("[{}]({}): {}", rustsec.id, rustsec.url, rustsec.title),
+                })
+            })
+            .collect();
+        sections.push(json!({ "activityTitle": "New advisories", "facts": facts }));
+    }
+    if !digest.resolved.is_empty() {
+        sections.push(json!({
+            "activityTitle": "Resolved advisories",
+            "text": digest.resolved.join(", "),
+        }));
+    }
+    let mut text = format!("{} advisories reported in total", digest.total);
+    if let Some(more) = more(digest) {
+        text = format!("{more}. {text}");
+    }
+    json!({
+        "@type": "MessageCard",
+        "@context": "https://schema.org/extensions",
+        "summary": headline,
+        "themeColor": color,
+        "title": headline,
+        "text": text,
+        "sections": sections,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{State, WebhookFormat, digest, escape, headline, notify_changes, payload};
+    use crate::{
+        advisory::{parse, test::TEST_REPORT},
+        config::test::config,
+        policy::evaluate,
+    };
+    use anyhow::Result;
+    use reqwest::Client;
+    use serde_json::json;
+    use std::{env, fs, path::PathBuf, process};
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{body_partial_json, method, path},
+    };
+
+    fn state_file(name: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("audit-check-{}-{name}.json", process::id()));
+        drop(fs::remove_file(&path));
+        path
+    }
+
+    #[test]
+    fn digest_works() -> Result<()> {
+        let findings = evaluate(&config(), parse(TEST_REPORT)?);
+        let previous = State {
+            advisories: [
+                "RUSTSEC-2020-0099 aovec 1.1.0",
+                "RUSTSEC-2017-0001 old 0.1.0",
+            ]
+            .map(ToString::to_string)
+            .into(),
+        };
+        let (digest, state) = digest(&previous, &findings);
+        assert_eq!(4, state.advisories.len());
+        assert_eq!(3, digest.new.len());
+        assert_eq!(vec!["RUSTSEC-2017-0001 old 0.1.0"], digest.resolved);
+        assert_eq!(
+            "cargo audit: 3 new advisories, 1 resolved in a/b",
+            headline(&digest, Some("a/b"))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn payload_works() -> Result<()> {
+        let findings = evaluate(&config(), parse(TEST_REPORT)?);
+        let (digest, _) = digest(&State::default(), &findings);
+
+        let json = payload(WebhookFormat::Json, &digest, Some("a/b"));
+        assert_eq!("cargo audit: 4 new advisories in a/b", json["text"]);
+        assert_eq!("RUSTSEC-2020-0099", json["new"][0]["id"]);
+
+        let slack = payload(WebhookFormat::Slack, &digest, None);
+        assert_eq!("header", slack["blocks"][0]["type"]);
+        assert_eq!(Some(6), slack["blocks"].as_array().map(Vec::len));
+        let aovec = slack["blocks"][1]["text"]["text"]
+            .as_str()
+            .unwrap_or_default();
+        assert!(aovec.contains("Aovec&lt;T&gt; lacks bound"));
+
+        let teams = payload(WebhookFormat::Teams, &digest, None);
+        assert_eq!("MessageCard", teams["@type"]);
+        assert_eq!("CF222E", teams["themeColor"]);
+        assert_eq!("aovec 1.1.0", teams["sections"][0]["facts"][0]["name"]);
+        Ok(())
+    }
+
+    #[test]
+    fn slack_links_are_escaped() -> Result<()> {
+        let mut findings = evaluate(&config(), parse(TEST_REPORT)?);
+        findings[0].rustsec.url = "https://example.com/?a=1&b=2|x>y".to_string();
+        let (digest, _) = digest(&State::default(), &findings);
+        let slack = payload(WebhookFormat::Slack, &digest, None);
+        let text = slack["blocks"][1]["text"]["text"]
+            .as_str()
+            .unwrap_or_default();
+        assert!(text.starts_with("*<https://example.com/?a=1&amp;b=2%7Cx&gt;y|RUSTSEC-"));
+        Ok(())
+    }
+
+    #[test]
+    fn escape_works() {
+        assert_eq!("&lt;a|b&gt; &amp;&amp;", escape("<a|b> &&"));
+    }
+
+    #[tokio::test]
+    async fn notify_changes_only_posts_changes() -> Result<()> {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/hook"))
+            .and(body_partial_json(
+                json!({ "text": "cargo audit: 4 new advisories" }),
+            ))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let url = format!("{}/hook", server.uri());
+        let state_file = state_file("changes");
+        let state = state_file.to_string_lossy();
+        let findings = evaluate(&config(), parse(TEST_REPORT)?);
+        let client = Client::new();
+
+        let notify = || {
+            notify_changes(
+                &client,
+                &url,
+                WebhookFormat::Json,
+                &state,
+                None,
+                &findings,
+                false,
+            )
+        };
+        assert!(notify().await?);
+        // the same advisories again are not posted
+        assert!(!notify().await?);
+        drop(fs::remove_file(&state_file));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn notify_changes_retries_unavailable_webhooks() -> Result<()> {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(503).insert_header("retry-after", "0"))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let state_file = state_file("retry");
+        let findings = evaluate(&config(), parse(TEST_REPORT)?);
+
+        let posted = notify_changes(
+            &Client::new(),
+            &server.uri(),
+            WebhookFormat::Json,
+            &state_file.to_string_lossy(),
+            None,
+            &findings,
+            false,
+        )
+        .await;
+        drop(fs::remove_file(&state_file));
+        assert!(posted?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn notify_changes_keeps_state_on_failure() -> Result<()> {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(500).set_body_string("boom"))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let state_file = state_file("failure");
+        let findings = evaluate(&config(), parse(TEST_REPORT)?);
+
+        let res = notify_changes(
+            &Client::new(),
+            &server.uri(),
+            WebhookFormat::Slack,
+            &state_file.to_string_lossy(),
+            None,
+            &findings,
+            false,
+        )
+        .await;
+        assert!(res.is_err_and(|e| e.to_string().contains("500")));
+        assert!(!state_file.exists());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn notify_changes_skips_dry_runs() -> Result<()> {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&server)
+            .await;
+        let state_file = state_file("dry-run");
+        let findings = evaluate(&config(), parse(TEST_REPORT)?);
+
+        let posted = notify_changes(
+            &Client::new(),
+            &server.uri(),
+            WebhookFormat::Json,
+            &state_file.to_string_lossy(),
+            None,
+            &findings,
+            true,
+        )
+        .await?;
+        assert!(!posted);
+        assert!(!state_file.exists());
+        Ok(())
+    }
+}